@@ -0,0 +1,272 @@
+//! Derives parsers for structs read from fixed line templates, e.g.
+//!
+//! ```ignore
+//! #[derive(LineFormat)]
+//! #[line("{name} = ({left}, {right})")]
+//! struct Node<'a> {
+//!     name: &'a str,
+//!     left: &'a str,
+//!     right: &'a str,
+//! }
+//! ```
+//!
+//! The derive generates `fn from_span(span: Span<'a>) -> parse::Result<Self>`
+//! against the `parse` module of the crate using it, plus a `FromStr` impl
+//! for structs that don't borrow from the input.
+//!
+//! Each `{field}` matches everything up to the next literal in the template
+//! (or the rest of the line, if it comes last), and is converted according
+//! to its type:
+//!
+//! - `&str` fields borrow the matched text as is,
+//! - `Span` fields keep the matched span, for parsing it further,
+//! - anything else is trimmed and parsed with `FromStr`.
+//!
+//! As in `format!`, `{{` and `}}` stand for literal braces.
+
+use std::mem;
+
+use proc_macro::TokenStream;
+use proc_macro2::Span;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Error, Fields, LitStr, Type};
+
+enum Segment {
+    Literal(String),
+    Field(String),
+}
+
+fn parse_template(template: &LitStr) -> Result<Vec<Segment>, Error> {
+    let error = |message| Error::new(template.span(), message);
+    let mut segments = Vec::new();
+    let mut literal = String::new();
+    let text = template.value();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.as_str().starts_with('{') => {
+                chars.next();
+                literal.push('{');
+            }
+            '}' if chars.as_str().starts_with('}') => {
+                chars.next();
+                literal.push('}');
+            }
+            '{' => {
+                let (field, rest) =
+                    chars.as_str().split_once('}').ok_or_else(|| {
+                        error(
+                            "unclosed `{` in template (`{{` is a literal `{`)",
+                        )
+                    })?;
+                chars = rest.chars();
+                if !literal.is_empty() {
+                    segments.push(Segment::Literal(mem::take(&mut literal)));
+                } else if matches!(segments.last(), Some(Segment::Field(_))) {
+                    return Err(error(
+                        "fields must be separated by some literal text",
+                    ));
+                }
+                segments.push(Segment::Field(field.trim().to_string()));
+            }
+            '}' => {
+                return Err(error(
+                    "unmatched `}` in template (`}}` is a literal `}`)",
+                ))
+            }
+            c => literal.push(c),
+        }
+    }
+    if !literal.is_empty() {
+        segments.push(Segment::Literal(literal));
+    }
+    Ok(segments)
+}
+
+enum FieldKind {
+    Str,
+    Span,
+    FromStr,
+}
+
+fn field_kind(ty: &Type) -> FieldKind {
+    match ty {
+        Type::Reference(r) => match &*r.elem {
+            Type::Path(p) if p.path.is_ident("str") => FieldKind::Str,
+            _ => FieldKind::FromStr,
+        },
+        Type::Path(p) => match p.path.segments.last() {
+            Some(segment) if segment.ident == "Span" => FieldKind::Span,
+            _ => FieldKind::FromStr,
+        },
+        _ => FieldKind::FromStr,
+    }
+}
+
+#[proc_macro_derive(LineFormat, attributes(line))]
+pub fn derive_line_format(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+fn expand(input: DeriveInput) -> Result<proc_macro2::TokenStream, Error> {
+    let name = &input.ident;
+    let template: LitStr = input
+        .attrs
+        .iter()
+        .find(|attr| attr.path().is_ident("line"))
+        .ok_or_else(|| {
+            Error::new(Span::call_site(), "missing #[line(\"...\")] template")
+        })?
+        .parse_args()?;
+    let Data::Struct(data) = &input.data else {
+        return Err(Error::new(
+            name.span(),
+            "LineFormat only supports structs",
+        ));
+    };
+    let Fields::Named(fields) = &data.fields else {
+        return Err(Error::new(
+            name.span(),
+            "LineFormat only supports structs with named fields",
+        ));
+    };
+
+    let segments = parse_template(&template)?;
+    let placeholders: Vec<_> = segments
+        .iter()
+        .filter_map(|segment| match segment {
+            Segment::Field(field) => Some(field.as_str()),
+            Segment::Literal(_) => None,
+        })
+        .collect();
+    if let Some(unknown) = placeholders.iter().find(|&&p| {
+        !fields.named.iter().any(|f| f.ident.as_ref().unwrap() == p)
+    }) {
+        return Err(Error::new(
+            template.span(),
+            format!("`{{{unknown}}}` is not a field"),
+        ));
+    }
+    for field in &fields.named {
+        let ident = field.ident.as_ref().unwrap();
+        let count = placeholders.iter().filter(|&&p| ident == p).count();
+        if count != 1 {
+            return Err(Error::new(
+                ident.span(),
+                format!(
+                    "`{{{ident}}}` must appear exactly once in the template"
+                ),
+            ));
+        }
+    }
+
+    // each step consumes the front of `rest`
+    let mut steps = Vec::new();
+    for (i, segment) in segments.iter().enumerate() {
+        match segment {
+            Segment::Literal(literal) => {
+                if i > 0 {
+                    // already consumed by the preceding field
+                    continue;
+                }
+                steps.push(quote! {
+                    let rest = rest.strip_prefix(#literal)?;
+                });
+            }
+            Segment::Field(field) => {
+                let ident = syn::Ident::new(field, Span::call_site());
+                let field = fields
+                    .named
+                    .iter()
+                    .find(|f| f.ident.as_ref().unwrap() == &ident)
+                    .unwrap();
+                let ty = &field.ty;
+                let matched = match segments.get(i + 1) {
+                    Some(Segment::Literal(literal)) => {
+                        let message =
+                            format!("expected `{literal}` after `{{{ident}}}`");
+                        quote! {
+                            let (matched, rest) = rest
+                                .split_once(#literal)
+                                .map_err(|_| rest.error(#message))?;
+                        }
+                    }
+                    _ => quote! {
+                        let (matched, rest) = (rest, rest.end());
+                    },
+                };
+                let value = match field_kind(ty) {
+                    FieldKind::Str => quote! { matched.as_str() },
+                    FieldKind::Span => quote! { matched },
+                    FieldKind::FromStr => {
+                        let ty_name = quote!(#ty).to_string();
+                        quote! {{
+                            let matched = matched.trim();
+                            matched.as_str().parse::<#ty>().map_err(|_| {
+                                matched.error(format!(
+                                    "`{}`: expected {}, found `{}`",
+                                    stringify!(#ident),
+                                    #ty_name,
+                                    matched.as_str(),
+                                ))
+                            })?
+                        }}
+                    }
+                };
+                steps.push(quote! {
+                    #matched
+                    let #ident: #ty = #value;
+                });
+            }
+        }
+    }
+    let idents = fields.named.iter().map(|f| f.ident.as_ref().unwrap());
+
+    let (impl_generics, ty_generics, where_clause) =
+        input.generics.split_for_impl();
+    let lifetime = match input.generics.lifetimes().next() {
+        Some(def) => {
+            let lifetime = &def.lifetime;
+            quote!(#lifetime)
+        }
+        None => quote!('_),
+    };
+    let from_str = if input.generics.params.is_empty() {
+        quote! {
+            impl ::std::str::FromStr for #name {
+                type Err = crate::parse::ParseError;
+
+                fn from_str(s: &str) -> crate::parse::Result<Self> {
+                    Self::from_span(crate::parse::Span::new(s))
+                }
+            }
+        }
+    } else {
+        quote! {}
+    };
+
+    Ok(quote! {
+        impl #impl_generics #name #ty_generics #where_clause {
+            #[allow(dead_code)]
+            pub fn from_span(
+                span: crate::parse::Span<#lifetime>,
+            ) -> crate::parse::Result<Self> {
+                let rest = span;
+                #(#steps)*
+                if !rest.is_empty() {
+                    return Err(rest.error(format!(
+                        "unexpected `{}` after {}",
+                        rest.as_str(),
+                        #template,
+                    )));
+                }
+                Ok(Self { #(#idents),* })
+            }
+        }
+
+        #from_str
+    })
+}
@@ -0,0 +1,7 @@
+//! Templates and structs the derive must reject, with the errors it gives.
+//! Run with `TRYBUILD=overwrite` to update the expected errors.
+
+#[test]
+fn compile_fail() {
+    trybuild::TestCases::new().compile_fail("tests/ui/*.rs");
+}
@@ -0,0 +1,22 @@
+//! Templates the derive must accept, parsed with the main crate's `Span`.
+
+#[path = "../../src/parse.rs"]
+pub mod parse;
+
+use aoc2023_derive::LineFormat;
+use parse::Span;
+
+#[test]
+fn escaped_braces() {
+    #[derive(LineFormat)]
+    #[line("{{{name}}} = {value}")]
+    struct Braced<'a> {
+        name: &'a str,
+        value: u32,
+    }
+
+    let braced = Braced::from_span(Span::new("{x} = 5")).unwrap();
+    assert_eq!((braced.name, braced.value), ("x", 5));
+    let err = Braced::from_span(Span::new("x} = 5")).err().unwrap();
+    assert_eq!(err.to_string(), "line 1, column 1: expected `{`");
+}
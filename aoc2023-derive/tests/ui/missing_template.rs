@@ -0,0 +1,8 @@
+use aoc2023_derive::LineFormat;
+
+#[derive(LineFormat)]
+struct One {
+    a: u32,
+}
+
+fn main() {}
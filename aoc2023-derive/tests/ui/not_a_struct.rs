@@ -0,0 +1,9 @@
+use aoc2023_derive::LineFormat;
+
+#[derive(LineFormat)]
+#[line("{a}")]
+enum One {
+    A,
+}
+
+fn main() {}
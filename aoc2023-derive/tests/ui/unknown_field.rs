@@ -0,0 +1,10 @@
+use aoc2023_derive::LineFormat;
+
+#[derive(LineFormat)]
+#[line("{a} and {c}")]
+struct Pair {
+    a: u32,
+    b: u32,
+}
+
+fn main() {}
@@ -0,0 +1,166 @@
+//! Aho–Corasick string matching: finds every occurrence of any of a set of
+//! patterns, overlaps included, in a single pass over the haystack.
+
+use std::collections::VecDeque;
+
+/// A match of one of the patterns, at `start..end` in the haystack.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Match<'a, V> {
+    pub start: usize,
+    pub end: usize,
+    pub value: &'a V,
+}
+
+/// An automaton over bytes that matches a set of patterns, each carrying a
+/// value.
+#[derive(Debug, Clone)]
+pub struct AhoCorasick<V> {
+    /// The next state for each state and byte, with failure links already
+    /// followed, so matching never backtracks.
+    next: Vec<[u32; 256]>,
+    /// The patterns that end at each state, longest first.
+    outputs: Vec<Vec<usize>>,
+    /// Each pattern's length and value.
+    patterns: Vec<(usize, V)>,
+}
+
+const ROOT: usize = 0;
+const NONE: u32 = u32::MAX;
+
+impl<V> AhoCorasick<V> {
+    /// # Panics
+    ///
+    /// If a pattern is empty.
+    pub fn new<P: AsRef<[u8]>>(
+        patterns: impl IntoIterator<Item = (P, V)>,
+    ) -> Self {
+        let mut automaton = Self {
+            next: vec![[NONE; 256]],
+            outputs: vec![vec![]],
+            patterns: Vec::new(),
+        };
+
+        // build a trie of the patterns
+        for (pattern, value) in patterns {
+            let pattern = pattern.as_ref();
+            assert!(!pattern.is_empty(), "empty pattern");
+            let mut state = ROOT;
+            for &b in pattern {
+                if automaton.next[state][b as usize] == NONE {
+                    automaton.next[state][b as usize] =
+                        automaton.next.len() as u32;
+                    automaton.next.push([NONE; 256]);
+                    automaton.outputs.push(vec![]);
+                }
+                state = automaton.next[state][b as usize] as usize;
+            }
+            automaton.outputs[state].push(automaton.patterns.len());
+            automaton.patterns.push((pattern.len(), value));
+        }
+
+        // then fill in the missing transitions breadth first, so that every
+        // state's failure state (its longest proper suffix in the trie) is
+        // complete before the state itself
+        let mut fail = vec![ROOT; automaton.next.len()];
+        let mut queue = VecDeque::from([ROOT]);
+        while let Some(state) = queue.pop_front() {
+            for b in 0..256 {
+                let child = automaton.next[state][b];
+                if child == NONE {
+                    automaton.next[state][b] = if state == ROOT {
+                        ROOT as u32
+                    } else {
+                        automaton.next[fail[state]][b]
+                    };
+                    continue;
+                }
+                let child = child as usize;
+                if state != ROOT {
+                    fail[child] = automaton.next[fail[state]][b] as usize;
+                }
+                // the suffix's patterns are shorter, so they go last
+                let inherited = automaton.outputs[fail[child]].clone();
+                automaton.outputs[child].extend(inherited);
+                queue.push_back(child);
+            }
+        }
+        automaton
+    }
+
+    /// Every match in `haystack`, overlaps included, ordered by where they
+    /// end (and longest first for matches that end at the same place).
+    pub fn find_overlapping<'a>(
+        &'a self,
+        haystack: &'a [u8],
+    ) -> impl Iterator<Item = Match<'a, V>> + 'a {
+        let mut state = ROOT;
+        haystack.iter().enumerate().flat_map(move |(i, &b)| {
+            state = self.next[state][b as usize] as usize;
+            self.outputs[state].iter().map(move |&p| {
+                let (len, ref value) = self.patterns[p];
+                Match {
+                    start: i + 1 - len,
+                    end: i + 1,
+                    value,
+                }
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches<'a>(
+        ac: &AhoCorasick<&'a str>,
+        haystack: &str,
+    ) -> Vec<(usize, &'a str)> {
+        ac.find_overlapping(haystack.as_bytes())
+            .map(|m| (m.start, *m.value))
+            .collect()
+    }
+
+    #[test]
+    fn classic() {
+        let ac = AhoCorasick::new(["he", "she", "his", "hers"].map(|p| (p, p)));
+        assert_eq!(
+            matches(&ac, "ushers"),
+            [(1, "she"), (2, "he"), (2, "hers")]
+        );
+        assert_eq!(matches(&ac, "ahishers").len(), 4);
+        assert_eq!(matches(&ac, "xyz"), []);
+    }
+
+    #[test]
+    fn overlapping_words() {
+        let ac =
+            AhoCorasick::new(["one", "two", "eight", "nine"].map(|p| (p, p)));
+        assert_eq!(matches(&ac, "twone"), [(0, "two"), (2, "one")]);
+        assert_eq!(matches(&ac, "eightwo"), [(0, "eight"), (4, "two")]);
+        assert_eq!(
+            matches(&ac, "nineightwone"),
+            [(0, "nine"), (3, "eight"), (7, "two"), (9, "one")]
+        );
+    }
+
+    #[test]
+    fn nested_patterns() {
+        let ac = AhoCorasick::new([("a", 1), ("aa", 2), ("aaa", 3)]);
+        let found: Vec<_> = ac
+            .find_overlapping(b"aaa")
+            .map(|m| (m.start, m.end, *m.value))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                (0, 1, 1),
+                (0, 2, 2),
+                (1, 2, 1),
+                (0, 3, 3),
+                (1, 3, 2),
+                (2, 3, 1)
+            ]
+        );
+    }
+}
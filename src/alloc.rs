@@ -0,0 +1,70 @@
+//! A global allocator for test builds that counts what it hands out, so
+//! benches can report how much memory a parser needs as well as how long it
+//! takes.
+//!
+//! The counts are process-wide, so only measure from one test at a time, e.g.
+//! `cargo test --release bench_ -- --ignored --nocapture --test-threads 1`.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+struct Counting;
+
+static LIVE: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let live = LIVE.fetch_add(layout.size(), Ordering::Relaxed);
+        PEAK.fetch_max(live + layout.size(), Ordering::Relaxed);
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        LIVE.fetch_sub(layout.size(), Ordering::Relaxed);
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static COUNTING: Counting = Counting;
+
+/// What running something cost.
+#[derive(Debug, Clone, Copy)]
+pub struct Usage {
+    /// The most memory allocated at once, beyond what was already live.
+    pub peak_bytes: usize,
+    pub allocations: usize,
+    pub time: Duration,
+}
+
+impl std::fmt::Display for Usage {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "{:.1} MB peak in {} allocations, {:?}",
+            self.peak_bytes as f64 / (1 << 20) as f64,
+            self.allocations,
+            self.time
+        )
+    }
+}
+
+/// Runs `f`, counting the memory it allocates, including whatever it
+/// returns.
+pub fn measure<T>(f: impl FnOnce() -> T) -> (T, Usage) {
+    let live = LIVE.load(Ordering::Relaxed);
+    PEAK.store(live, Ordering::Relaxed);
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+    let start = Instant::now();
+    let result = f();
+    let usage = Usage {
+        time: start.elapsed(),
+        peak_bytes: PEAK.load(Ordering::Relaxed) - live,
+        allocations: ALLOCATIONS.load(Ordering::Relaxed) - allocations,
+    };
+    (result, usage)
+}
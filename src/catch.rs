@@ -0,0 +1,68 @@
+//! Runs solvers with panics turned into error messages, for the modes that
+//! need to survive a bad input (minimizing, serving) instead of aborting.
+
+use std::any::Any;
+use std::cell::Cell;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Mutex;
+
+use crate::Solver;
+
+static LAST_PANIC: Mutex<Option<String>> = Mutex::new(None);
+
+thread_local! {
+    /// Whether this thread is inside [`catch`].
+    static CATCHING: Cell<bool> = const { Cell::new(false) };
+}
+
+fn payload_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(s) = payload.downcast_ref::<&str>() {
+        s.to_string()
+    } else if let Some(s) = payload.downcast_ref::<String>() {
+        s.clone()
+    } else {
+        "<non-string panic>".to_string()
+    }
+}
+
+/// Replaces the panic hook with one that records `location: message` for
+/// [`run`] instead of printing it. Panics outside [`catch`] still go to the
+/// hook that was there before.
+pub fn quiet_panics() {
+    let loud = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        if !CATCHING.get() {
+            return loud(info);
+        }
+        let location = info
+            .location()
+            .map(|l| format!("{}:{}", l.file(), l.line()))
+            .unwrap_or_default();
+        let message = payload_message(info.payload());
+        let mut last = LAST_PANIC.lock().unwrap();
+        if last.is_none() {
+            *last = Some(format!("{location}: {message}"));
+        }
+    }));
+}
+
+/// Runs `solver`, returning the panic message if it panicked. The message
+/// includes the panic location when [`quiet_panics`] is in effect.
+pub fn run(solver: Solver, input: Option<String>) -> Result<u64, String> {
+    catch(|| solver(input))
+}
+
+/// Like [`run`], for any way of getting an answer.
+pub fn catch(answer: impl FnOnce() -> u64) -> Result<u64, String> {
+    *LAST_PANIC.lock().unwrap() = None;
+    let catching = CATCHING.replace(true);
+    let result = panic::catch_unwind(AssertUnwindSafe(answer));
+    CATCHING.set(catching);
+    result.map_err(|payload| {
+        LAST_PANIC
+            .lock()
+            .unwrap()
+            .take()
+            .unwrap_or_else(|| payload_message(&*payload))
+    })
+}
@@ -0,0 +1,157 @@
+//! Cycle detection for state machines that are run far more steps than can
+//! be simulated: find where the states start repeating, then jump ahead.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// The states visited from some start until they start repeating: after
+/// `prefix` steps, the states cycle with period `len`.
+#[derive(Debug, Clone)]
+pub struct Cycle<S> {
+    pub prefix: usize,
+    pub len: usize,
+    /// Every distinct state, in order.
+    states: Vec<S>,
+}
+
+impl<S> Cycle<S> {
+    /// Turns step `n` into the index of the same state in `states`.
+    fn index(&self, n: usize) -> usize {
+        if n < self.prefix {
+            n
+        } else {
+            self.prefix + (n - self.prefix) % self.len
+        }
+    }
+
+    /// The state after `n` steps, for any `n`.
+    pub fn state_at(&self, n: usize) -> &S {
+        &self.states[self.index(n)]
+    }
+
+    /// The states before the cycle starts.
+    #[cfg(test)]
+    pub fn prefix_states(&self) -> &[S] {
+        &self.states[..self.prefix]
+    }
+
+    /// One period of the cycle's states, starting at step `prefix`.
+    #[cfg(test)]
+    pub fn cycle_states(&self) -> &[S] {
+        &self.states[self.prefix..]
+    }
+}
+
+/// Runs `step` from `start` until a state repeats, remembering every state
+/// along the way.
+pub fn find_cycle<S: Clone + Eq + Hash>(
+    start: S,
+    mut step: impl FnMut(&S) -> S,
+) -> Cycle<S> {
+    let mut seen = HashMap::new();
+    let mut states = Vec::new();
+    let mut state = start;
+    loop {
+        if let Some(&prefix) = seen.get(&state) {
+            return Cycle {
+                prefix,
+                len: states.len() - prefix,
+                states,
+            };
+        }
+        seen.insert(state.clone(), states.len());
+        let next = step(&state);
+        states.push(state);
+        state = next;
+    }
+}
+
+/// Brent's algorithm: the same `(prefix, len)` as [`find_cycle`], in
+/// constant memory and without hashing, at the cost of running `step` a few
+/// times more.
+#[cfg(test)]
+pub fn brent<S: Clone + Eq>(
+    start: S,
+    mut step: impl FnMut(&S) -> S,
+) -> (usize, usize) {
+    // find the cycle length by moving the tortoise up to the hare at each
+    // power of two, until the hare runs into it
+    let mut power = 1;
+    let mut len = 1;
+    let mut tortoise = start.clone();
+    let mut hare = step(&start);
+    while tortoise != hare {
+        if power == len {
+            tortoise = hare.clone();
+            power *= 2;
+            len = 0;
+        }
+        hare = step(&hare);
+        len += 1;
+    }
+
+    // then walk two pointers `len` apart from the start until they meet at
+    // the start of the cycle
+    let mut tortoise = start.clone();
+    let mut hare = start;
+    for _ in 0..len {
+        hare = step(&hare);
+    }
+    let mut prefix = 0;
+    while tortoise != hare {
+        tortoise = step(&tortoise);
+        hare = step(&hare);
+        prefix += 1;
+    }
+    (prefix, len)
+}
+
+/// The state after `n` steps from `start`, found without running all `n`
+/// steps (as long as the states repeat well before then).
+#[cfg(test)]
+pub fn nth_state<S: Clone + Eq + Hash>(
+    start: S,
+    step: impl FnMut(&S) -> S,
+    n: usize,
+) -> S {
+    find_cycle(start, step).state_at(n).clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // x -> x^2 + 1 mod 255 from 3 goes 3, 10, 101, 2, 5, 26, 167, 95, 101, ...
+    fn step(x: &u32) -> u32 {
+        (x * x + 1) % 255
+    }
+
+    #[test]
+    fn finds_prefix_and_length() {
+        let cycle = find_cycle(3, step);
+        assert_eq!((cycle.prefix, cycle.len), (2, 6));
+        assert_eq!(cycle.prefix_states(), [3, 10]);
+        assert_eq!(cycle.cycle_states(), [101, 2, 5, 26, 167, 95]);
+        assert_eq!(brent(3, step), (2, 6));
+    }
+
+    #[test]
+    fn extrapolates() {
+        let mut x = 3;
+        let cycle = find_cycle(x, step);
+        for n in 0..100 {
+            assert_eq!(*cycle.state_at(n), x);
+            x = step(&x);
+        }
+        assert_eq!(nth_state(3, step, 1_000_000_000), 5);
+    }
+
+    #[test]
+    fn pure_cycles_and_fixed_points() {
+        let cycle = find_cycle(0, |x| (x + 1) % 7);
+        assert_eq!((cycle.prefix, cycle.len), (0, 7));
+        assert_eq!(brent(0, |x| (x + 1) % 7), (0, 7));
+        assert_eq!(brent(5, |x: &i32| (x / 2).max(1)), (2, 1));
+        assert_eq!(*find_cycle(5, |x: &i32| (x / 2).max(1)).state_at(99), 1);
+    }
+}
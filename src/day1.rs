@@ -1,4 +1,9 @@
-use std::collections::HashMap;
+use crate::aho_corasick::{AhoCorasick, Match};
+use crate::parse::{self, Span};
+use rayon::prelude::*;
+use std::cmp::Reverse;
+use std::fmt;
+use std::io::{BufRead, Read};
 
 fn part1_example_input() -> String {
     String::from_utf8_lossy(include_bytes!("day1_part1_example.txt"))
@@ -10,112 +15,974 @@ fn part2_example_input() -> String {
         .to_string()
 }
 
-pub fn part1(input: Option<String>) -> u64 {
-    let input = input.unwrap_or_else(part1_example_input);
-    let mut sum = 0;
-    for line in input.lines() {
-        let digits = line
-            .chars()
-            .filter(char::is_ascii_digit)
-            .map(|c| c.to_digit(10).unwrap() as u64)
-            .collect::<Vec<_>>();
-        sum += digits.first().unwrap() * 10 + digits.last().unwrap();
-    }
-    sum
-}
-
-fn digit_from_string(s: &str) -> u64 {
-    let spellings = HashMap::from([
-        ("one", 1),
-        ("two", 2),
-        ("three", 3),
-        ("four", 4),
-        ("five", 5),
-        ("six", 6),
-        ("seven", 7),
-        ("eight", 8),
-        ("nine", 9),
-    ]);
-    spellings[s]
+/// Spelled-out digits to look for in part 2, on top of the numerals.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Vocabulary {
+    words: Vec<(String, u64)>,
 }
 
-pub fn part2(input: Option<String>) -> u64 {
-    let prefixes = HashMap::from([
-        ('o', vec!["one"]),
-        ('t', vec!["two", "three"]),
-        ('f', vec!["four", "five"]),
-        ('s', vec!["six", "seven"]),
-        ('e', vec!["eight"]),
-        ('n', vec!["nine"]),
-    ]);
-    let suffixes = HashMap::from([
-        ('e', vec!["one", "three", "five", "nine"]),
-        ('n', vec!["seven"]),
-        ('o', vec!["two"]),
-        ('r', vec!["four"]),
-        ('t', vec!["eight"]),
-        ('x', vec!["six"]),
-    ]);
+impl Vocabulary {
+    /// The names of the built-in vocabularies, for `--lang`.
+    pub const LANGUAGES: [&'static str; 4] = ["en", "fr", "de", "es"];
 
-    let input = input.unwrap_or_else(part2_example_input);
-    let mut sum = 0;
-    for line in input.lines() {
-        let mut first = 0;
-        'outer: for (i, c) in line.char_indices() {
-            if c.is_ascii_digit() {
-                first = c.to_digit(10).unwrap() as u64;
-                break 'outer;
+    /// "one" through "nine".
+    pub fn english() -> Self {
+        Self::builtin("en").unwrap()
+    }
+
+    pub fn builtin(lang: &str) -> Option<Self> {
+        let words = match lang {
+            "en" => [
+                "one", "two", "three", "four", "five", "six", "seven", "eight",
+                "nine",
+            ],
+            "fr" => [
+                "un", "deux", "trois", "quatre", "cinq", "six", "sept", "huit",
+                "neuf",
+            ],
+            "de" => [
+                "eins", "zwei", "drei", "vier", "fünf", "sechs", "sieben",
+                "acht", "neun",
+            ],
+            "es" => [
+                "uno", "dos", "tres", "cuatro", "cinco", "seis", "siete",
+                "ocho", "nueve",
+            ],
+            _ => return None,
+        };
+        Some(Self {
+            words: words
+                .iter()
+                .zip(1..)
+                .map(|(w, d)| (w.to_string(), d))
+                .collect(),
+        })
+    }
+
+    /// Parses `word: digit` lines, skipping blank lines and `#` comments.
+    pub fn parse(input: &str) -> parse::Result<Self> {
+        let mut words = Vec::new();
+        for line in Span::new(input).lines() {
+            let line = line.trim();
+            if line.is_empty() || line.as_str().starts_with('#') {
+                continue;
             }
-            if prefixes.contains_key(&c) {
-                for &digit in &prefixes[&c] {
-                    let j = i + digit.len();
-                    if j <= line.len() {
-                        let substr = &line[i..j];
-                        if substr == digit {
-                            first = digit_from_string(substr);
-                            break 'outer;
-                        }
-                    }
+            let (word, value) = line.header()?;
+            if word.is_empty() {
+                return Err(word.error("expected a word"));
+            }
+            if words.iter().any(|(w, _)| w == word.as_str()) {
+                let message =
+                    format!("`{}` is in the vocabulary twice", word.as_str());
+                return Err(word.error(message));
+            }
+            let digit: u64 = value.int()?;
+            if digit > 9 {
+                return Err(
+                    value.error(format!("expected a digit, found `{digit}`"))
+                );
+            }
+            words.push((word.as_str().to_string(), digit));
+        }
+        Ok(Self { words })
+    }
+}
+
+fn numerals() -> impl Iterator<Item = (String, u64)> {
+    (0..=9).map(|d| (d.to_string(), d))
+}
+
+/// A number found in a line, at `start..end`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Token {
+    pub start: usize,
+    pub end: usize,
+    pub value: u64,
+}
+
+impl Token {
+    fn len(&self) -> usize {
+        self.end - self.start
+    }
+}
+
+impl From<Match<'_, u64>> for Token {
+    fn from(m: Match<u64>) -> Self {
+        Self {
+            start: m.start,
+            end: m.end,
+            value: *m.value,
+        }
+    }
+}
+
+/// The token that starts first and the one that ends last, preferring the
+/// longer token on ties, so overlapping words like "twone" count as both 2
+/// and 1, and "eighteen" wins over the "eight" inside it.
+fn first_and_last(
+    tokens: impl IntoIterator<Item = Token>,
+) -> Option<(Token, Token)> {
+    let mut first: Option<Token> = None;
+    let mut last: Option<Token> = None;
+    for t in tokens {
+        if first.is_none_or(|f| {
+            (t.start, Reverse(t.len())) < (f.start, Reverse(f.len()))
+        }) {
+            first = Some(t);
+        }
+        if last.is_none_or(|l| (t.end, t.len()) > (l.end, l.len())) {
+            last = Some(t);
+        }
+    }
+    Some((first?, last?))
+}
+
+/// How the first and last tokens on a line make up its calibration value
+/// when they can have more than one digit, like "twenty-three".
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum MultiDigitRule {
+    /// The first token's leading digit and the last token's trailing digit,
+    /// as if the numbers had been written in digits.
+    #[default]
+    Edges,
+    /// Both tokens' leading digits.
+    Leading,
+    /// Both tokens' trailing digits.
+    Trailing,
+    /// Both tokens' whole values, one after the other.
+    Concat,
+}
+
+impl MultiDigitRule {
+    /// The rules' names, for `--compound`.
+    pub const NAMES: [&'static str; 4] =
+        ["edges", "leading", "trailing", "concat"];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "edges" => Some(Self::Edges),
+            "leading" => Some(Self::Leading),
+            "trailing" => Some(Self::Trailing),
+            "concat" => Some(Self::Concat),
+            _ => None,
+        }
+    }
+
+    fn combine(self, first: u64, last: u64) -> u64 {
+        fn leading(mut n: u64) -> u64 {
+            while n >= 10 {
+                n /= 10;
+            }
+            n
+        }
+        fn trailing(n: u64) -> u64 {
+            n % 10
+        }
+
+        match self {
+            Self::Edges => leading(first) * 10 + trailing(last),
+            Self::Leading => leading(first) * 10 + leading(last),
+            Self::Trailing => trailing(first) * 10 + trailing(last),
+            Self::Concat => {
+                let width = last.checked_ilog10().unwrap_or(0) + 1;
+                first * 10_u64.pow(width) + last
+            }
+        }
+    }
+}
+
+/// The words that compound numbers are made of.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Atom {
+    Numeral(u64),
+    /// "zero" to "nine"
+    Unit(u64),
+    /// "ten" to "nineteen"
+    Teen(u64),
+    /// "twenty" to "ninety"
+    Tens(u64),
+    Hundred,
+}
+
+const UNITS: [&str; 10] = [
+    "zero", "one", "two", "three", "four", "five", "six", "seven", "eight",
+    "nine",
+];
+const TEENS: [&str; 10] = [
+    "ten",
+    "eleven",
+    "twelve",
+    "thirteen",
+    "fourteen",
+    "fifteen",
+    "sixteen",
+    "seventeen",
+    "eighteen",
+    "nineteen",
+];
+const TENS: [&str; 8] = [
+    "twenty", "thirty", "forty", "fifty", "sixty", "seventy", "eighty",
+    "ninety",
+];
+
+/// What can come between the words of a compound number.
+const SEPARATORS: [&str; 2] = ["-", " "];
+const AFTER_HUNDRED: [&str; 3] = ["-", " ", " and "];
+
+/// Finds English numbers up to 999, like "eleven", "twenty-three" or "one
+/// hundred and five", whose words may also run together.
+#[derive(Debug, Clone)]
+pub struct Compounds {
+    atoms: AhoCorasick<Atom>,
+}
+
+impl Compounds {
+    pub fn new() -> Self {
+        let words = |words: &'static [&str],
+                     values: Vec<u64>,
+                     atom: fn(u64) -> Atom| {
+            words
+                .iter()
+                .zip(values)
+                .map(move |(w, v)| (w.to_string(), atom(v)))
+        };
+        let atoms = numerals()
+            .map(|(n, d)| (n, Atom::Numeral(d)))
+            .chain(words(&UNITS, (0..10).collect(), Atom::Unit))
+            .chain(words(&TEENS, (10..20).collect(), Atom::Teen))
+            .chain(words(&TENS, (20..100).step_by(10).collect(), Atom::Tens))
+            .chain([("hundred".to_string(), Atom::Hundred)]);
+        Self {
+            atoms: AhoCorasick::new(atoms),
+        }
+    }
+
+    /// Every number in `line`, including each way of reading compound
+    /// numbers (so "twenty-three" also gives "twenty" and "three").
+    fn tokens(&self, line: &str) -> Vec<Token> {
+        let mut starting = vec![Vec::new(); line.len() + 1];
+        for m in self.atoms.find_overlapping(line.as_bytes()) {
+            starting[m.start].push((m.end, *m.value));
+        }
+        let atoms = Atoms {
+            line: line.as_bytes(),
+            starting,
+        };
+        let mut tokens = Vec::new();
+        for (start, at) in atoms.starting.iter().enumerate() {
+            for &(end, atom) in at {
+                for (end, value) in atoms.numbers(end, atom) {
+                    tokens.push(Token { start, end, value });
                 }
             }
         }
+        tokens
+    }
+}
+
+impl Default for Compounds {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
-        let mut last = 0;
-        'outer: for (i, c) in line.char_indices().rev() {
-            if c.is_ascii_digit() {
-                last = c.to_digit(10).unwrap() as u64;
-                break 'outer;
+/// The atoms found in a line, for putting together into numbers.
+struct Atoms<'a> {
+    line: &'a [u8],
+    /// The `(end, atom)`s starting at each position.
+    starting: Vec<Vec<(usize, Atom)>>,
+}
+
+impl Atoms<'_> {
+    /// The atoms right after `end`, or after one of `separators`.
+    fn following<'s>(
+        &'s self,
+        end: usize,
+        separators: &'s [&str],
+    ) -> impl Iterator<Item = (usize, Atom)> + 's {
+        let after_separators = separators
+            .iter()
+            .filter(move |sep| self.line[end..].starts_with(sep.as_bytes()))
+            .map(move |sep| end + sep.len());
+        std::iter::once(end)
+            .chain(after_separators)
+            .flat_map(|pos| self.starting[pos].iter().copied())
+    }
+
+    /// Every `(end, value)` of numbers that start with `atom`, which ends at
+    /// `end`.
+    fn numbers(&self, end: usize, atom: Atom) -> Vec<(usize, u64)> {
+        match atom {
+            Atom::Numeral(d) => vec![(end, d)],
+            Atom::Unit(u) if u > 0 => {
+                let mut numbers = vec![(end, u)];
+                for (end, _) in self
+                    .following(end, &SEPARATORS)
+                    .filter(|&(_, atom)| atom == Atom::Hundred)
+                {
+                    numbers.push((end, u * 100));
+                    for (end, atom) in self.following(end, &AFTER_HUNDRED) {
+                        numbers.extend(
+                            self.under_hundred(end, atom)
+                                .into_iter()
+                                .filter(|&(_, n)| n > 0)
+                                .map(|(end, n)| (end, u * 100 + n)),
+                        );
+                    }
+                }
+                numbers
             }
-            if suffixes.contains_key(&c) {
-                for &digit in &suffixes[&c] {
-                    let j = i as isize - digit.len() as isize + 1;
-                    if j >= 0 {
-                        let substr = &line[j as usize..i + 1];
-                        if substr == digit {
-                            last = digit_from_string(substr);
-                            break 'outer;
-                        }
+            _ => self.under_hundred(end, atom),
+        }
+    }
+
+    fn under_hundred(&self, end: usize, atom: Atom) -> Vec<(usize, u64)> {
+        match atom {
+            Atom::Unit(n) | Atom::Teen(n) => vec![(end, n)],
+            Atom::Tens(tens) => {
+                let mut numbers = vec![(end, tens)];
+                for (end, atom) in self.following(end, &SEPARATORS) {
+                    if let Atom::Unit(u @ 1..) = atom {
+                        numbers.push((end, tens + u));
                     }
                 }
+                numbers
+            }
+            Atom::Numeral(_) | Atom::Hundred => vec![],
+        }
+    }
+}
+
+/// Reads calibration values out of lines.
+#[derive(Debug, Clone)]
+pub enum Calibrator {
+    /// Single digits, as numerals or as words.
+    Digits(AhoCorasick<u64>),
+    /// Numbers that can be more than one digit, combined by the rule.
+    Compounds(Compounds, MultiDigitRule),
+}
+
+impl Calibrator {
+    /// Only the numerals 0 to 9.
+    pub fn numerals() -> Self {
+        Self::Digits(AhoCorasick::new(numerals()))
+    }
+
+    /// The numerals and the words in `vocab`.
+    pub fn words(vocab: &Vocabulary) -> Self {
+        let digits = numerals().chain(vocab.words.iter().cloned());
+        Self::Digits(AhoCorasick::new(digits))
+    }
+
+    pub fn compounds(rule: MultiDigitRule) -> Self {
+        Self::Compounds(Compounds::new(), rule)
+    }
+
+    fn tokens(&self, line: &str) -> Vec<Token> {
+        match self {
+            Self::Digits(digits) => digits
+                .find_overlapping(line.as_bytes())
+                .map(Token::from)
+                .collect(),
+            Self::Compounds(compounds, _) => compounds.tokens(line),
+        }
+    }
+
+    fn combine(&self, first: Token, last: Token) -> u64 {
+        match self {
+            Self::Digits(_) => first.value * 10 + last.value,
+            Self::Compounds(_, rule) => rule.combine(first.value, last.value),
+        }
+    }
+
+    /// How line number `number` (1-based) was calibrated.
+    pub fn line_report(
+        &self,
+        number: usize,
+        line: &str,
+    ) -> parse::Result<LineReport> {
+        let Some((first, last)) = first_and_last(self.tokens(line)) else {
+            let message = format!("no digits in `{line}`");
+            return Err(Span::at_line(line, number).line_error(message));
+        };
+        Ok(LineReport {
+            line: number,
+            first: Found::new(line, first),
+            last: Found::new(line, last),
+            value: self.combine(first, last),
+        })
+    }
+
+    /// Calibrates each line of `input`.
+    pub fn line_reports<'a>(
+        &'a self,
+        input: impl BufRead + 'a,
+    ) -> impl Iterator<Item = parse::Result<LineReport>> + 'a {
+        input
+            .lines()
+            .enumerate()
+            .map(|(i, line)| self.line_report(i + 1, &line.unwrap()))
+    }
+
+    /// Calibrates every line of `input`, stopping at the first line without
+    /// digits unless `skip_missing` is set.
+    pub fn report(
+        &self,
+        input: impl BufRead,
+        skip_missing: bool,
+    ) -> parse::Result<Report> {
+        let mut report = Report::default();
+        for line in self.line_reports(input) {
+            match line {
+                Ok(line) => report.lines.push(line),
+                Err(err) if skip_missing => report.skipped.push(err.line),
+                Err(err) => return Err(err),
             }
         }
+        Ok(report)
+    }
+}
+
+/// Whether a number was written with digits or spelled out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+    Numeral,
+    Word,
+}
+
+/// The number at one end of a line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Found {
+    pub value: u64,
+    pub source: Source,
+    pub text: String,
+    /// 1-based, in bytes
+    pub column: usize,
+}
+
+impl Found {
+    fn new(line: &str, token: Token) -> Self {
+        let text = &line[token.start..token.end];
+        let source = if text.bytes().all(|b| b.is_ascii_digit()) {
+            Source::Numeral
+        } else {
+            Source::Word
+        };
+        Self {
+            value: token.value,
+            source,
+            text: text.to_string(),
+            column: token.start + 1,
+        }
+    }
+}
+
+impl fmt::Display for Found {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let source = match self.source {
+            Source::Numeral => "numeral",
+            Source::Word => "word",
+        };
+        write!(
+            f,
+            "{} ({source} `{}` at column {})",
+            self.value, self.text, self.column
+        )
+    }
+}
+
+/// How one line was calibrated.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineReport {
+    /// 1-based
+    pub line: usize,
+    pub first: Found,
+    pub last: Found,
+    pub value: u64,
+}
 
-        sum += first * 10 + last;
+impl fmt::Display for LineReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "line {}: {} from first {}, last {}",
+            self.line, self.value, self.first, self.last
+        )
     }
-    sum
+}
+
+/// How every line of an input was calibrated.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Report {
+    pub lines: Vec<LineReport>,
+    /// Line numbers of the lines without digits.
+    pub skipped: Vec<usize>,
+}
+
+impl Report {
+    pub fn sum(&self) -> u64 {
+        self.lines.iter().map(|line| line.value).sum()
+    }
+
+    /// A line summing up the skipped lines, if there were any.
+    pub fn skipped_summary(&self) -> Option<String> {
+        if self.skipped.is_empty() {
+            return None;
+        }
+        let skipped: Vec<_> =
+            self.skipped.iter().map(|n| n.to_string()).collect();
+        Some(format!(
+            "skipped {} line(s) without digits: {}",
+            skipped.len(),
+            skipped.join(", ")
+        ))
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for line in &self.lines {
+            writeln!(f, "{line}")?;
+        }
+        if let Some(summary) = self.skipped_summary() {
+            writeln!(f, "{summary}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Day 1's command line options.
+#[derive(Debug, Default)]
+pub struct Options {
+    /// How to read part 2's numbers, if not as English digit words.
+    pub calibrator: Option<Calibrator>,
+    /// Print how each line was calibrated.
+    pub report: bool,
+    /// Leave out lines without digits, instead of failing.
+    pub skip_missing: bool,
+}
+
+/// Runs `part` with `options`, on the example input if there's no `input`.
+pub fn run(
+    part: u8,
+    input: Option<Box<dyn BufRead>>,
+    options: &Options,
+) -> parse::Result<u64> {
+    let (example, calibrator) = match (part, &options.calibrator) {
+        (1, None) => (part1_example_input(), Calibrator::numerals()),
+        (1, Some(_)) => panic!("only part 2 reads numbers spelled out"),
+        (2, calibrator) => (
+            part2_example_input(),
+            calibrator
+                .clone()
+                .unwrap_or_else(|| Calibrator::words(&Vocabulary::english())),
+        ),
+        _ => panic!("day 1 has no part {part}"),
+    };
+    let input =
+        input.unwrap_or_else(|| Box::new(std::io::Cursor::new(example)));
+    if !options.report && !options.skip_missing {
+        return match (part, &options.calibrator) {
+            (1, None) => part1_blocks(input, BLOCK_SIZE),
+            _ => sum(input, &calibrator),
+        };
+    }
+    let report = calibrator.report(input, options.skip_missing)?;
+    if options.report {
+        print!("{report}");
+    } else if let Some(summary) = report.skipped_summary() {
+        eprintln!("{summary}");
+    }
+    Ok(report.sum())
+}
+
+/// The sum of every line's calibration value.
+fn sum(input: impl BufRead, calibrator: &Calibrator) -> parse::Result<u64> {
+    calibrator
+        .line_reports(input)
+        .map(|line| Ok(line?.value))
+        .sum()
+}
+
+pub fn part1(input: Option<String>) -> u64 {
+    let input = input.unwrap_or_else(part1_example_input);
+    sum_lines(input.as_bytes(), 0).unwrap().0
+}
+
+/// Part 1 without the fast path: line by line through
+/// [`Calibrator::numerals`], as an independent check on [`part1`].
+pub fn part1_lines(input: Option<String>) -> u64 {
+    let input = input.unwrap_or_else(part1_example_input);
+    sum(input.as_bytes(), &Calibrator::numerals()).unwrap()
+}
+
+/// How much of a streamed input part 1 reads at a time.
+const BLOCK_SIZE: usize = 1 << 24;
+
+/// Part 1, reading `input` in blocks of about `block_size` bytes that each
+/// get summed in parallel.
+fn part1_blocks(
+    mut input: impl BufRead,
+    block_size: usize,
+) -> parse::Result<u64> {
+    let (mut sum, mut lines) = (0, 0);
+    let mut block = Vec::new();
+    loop {
+        let read = input
+            .by_ref()
+            .take(block_size as u64)
+            .read_to_end(&mut block)
+            .unwrap();
+        let eof = read < block_size;
+        // leave any partial line at the end for the next block
+        let end = if eof {
+            block.len()
+        } else {
+            match block.iter().rposition(|&b| b == b'\n') {
+                Some(i) => i + 1,
+                None => continue,
+            }
+        };
+        let (block_sum, block_lines) = sum_lines(&block[..end], lines)?;
+        sum += block_sum;
+        lines += block_lines;
+        block.drain(..end);
+        if eof {
+            return Ok(sum);
+        }
+    }
+}
+
+/// The sum of the calibration values of whole lines, and how many lines
+/// there were.
+///
+/// This is the same as summing [`Calibrator::numerals`]' values, but scans
+/// in from each end of a line without allocating, and splits the buffer into
+/// chunks at line boundaries to sum in parallel.
+///
+/// A line without digits gets the same error as on the slow path, counting
+/// lines from `lines_before`.
+fn sum_lines(buf: &[u8], lines_before: usize) -> parse::Result<(u64, usize)> {
+    let chunks = split_lines(buf, rayon::current_num_threads() * 4);
+    let sums: Vec<_> =
+        chunks.par_iter().map(|chunk| sum_chunk(chunk)).collect();
+    let (mut sum, mut lines) = (0, 0);
+    for chunk_sum in sums {
+        match chunk_sum {
+            Ok((chunk_sum, chunk_lines)) => {
+                sum += chunk_sum;
+                lines += chunk_lines;
+            }
+            Err((i, line)) => {
+                let line = line.strip_suffix(b"\r").unwrap_or(line);
+                let line = String::from_utf8_lossy(line);
+                let number = lines_before + lines + i + 1;
+                let err = Calibrator::numerals().line_report(number, &line);
+                return Err(err.unwrap_err());
+            }
+        }
+    }
+    Ok((sum, lines))
+}
+
+/// Splits `buf` into about `parts` chunks, each made of whole lines.
+fn split_lines(buf: &[u8], parts: usize) -> Vec<&[u8]> {
+    let size = buf.len().div_ceil(parts).max(1);
+    let mut chunks = Vec::new();
+    let mut rest = buf;
+    while !rest.is_empty() {
+        let end = match rest.get(size..) {
+            Some(tail) => tail
+                .iter()
+                .position(|&b| b == b'\n')
+                .map_or(rest.len(), |i| size + i + 1),
+            None => rest.len(),
+        };
+        let (chunk, tail) = rest.split_at(end);
+        chunks.push(chunk);
+        rest = tail;
+    }
+    chunks
+}
+
+/// Sums a chunk's lines and counts them, or returns the index and text of
+/// the first line without digits.
+fn sum_chunk(chunk: &[u8]) -> Result<(u64, usize), (usize, &[u8])> {
+    let chunk = chunk.strip_suffix(b"\n").unwrap_or(chunk);
+    let (mut sum, mut lines) = (0, 0);
+    for line in chunk.split(|&b| b == b'\n') {
+        let first = line.iter().find(|b| b.is_ascii_digit());
+        let last = line.iter().rev().find(|b| b.is_ascii_digit());
+        let (Some(first), Some(last)) = (first, last) else {
+            return Err((lines, line));
+        };
+        sum += ((first - b'0') * 10 + (last - b'0')) as u64;
+        lines += 1;
+    }
+    Ok((sum, lines))
+}
+
+pub fn part2(input: Option<String>) -> u64 {
+    let input = input.unwrap_or_else(part2_example_input);
+    part2_stream(input.as_bytes())
+}
+
+pub fn part2_stream(input: impl BufRead) -> u64 {
+    sum(input, &Calibrator::words(&Vocabulary::english())).unwrap()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn value(calibrator: &Calibrator, line: &str) -> Option<u64> {
+        calibrator
+            .line_report(1, line)
+            .ok()
+            .map(|report| report.value)
+    }
+
     #[test]
     fn example_part1() {
         assert_eq!(part1(None), 142);
     }
 
+    #[test]
+    fn paths_agree() {
+        assert_eq!(part1_lines(None), 142);
+        let input = "a1b2\r\n9\n".to_string();
+        assert_eq!(part1_lines(Some(input.clone())), 12 + 99);
+        assert_eq!(part1(Some(input)), 12 + 99);
+    }
+
     #[test]
     fn example_part2() {
         assert_eq!(part2(None), 281);
     }
+
+    #[test]
+    fn overlapping_words() {
+        let digits = Calibrator::words(&Vocabulary::english());
+        assert_eq!(value(&digits, "twone"), Some(21));
+        assert_eq!(value(&digits, "eightwo"), Some(82));
+        assert_eq!(value(&digits, "oneight3nineight"), Some(18));
+        assert_eq!(value(&digits, "xtwox"), Some(22));
+        assert_eq!(value(&digits, "nothing"), None);
+    }
+
+    #[test]
+    fn builtin_languages() {
+        let calibrate = |lang, line| {
+            let vocab = Vocabulary::builtin(lang).unwrap();
+            sum(line, &Calibrator::words(&vocab)).unwrap()
+        };
+        assert_eq!(calibrate("fr", "xdeuxunx".as_bytes()), 21);
+        assert_eq!(calibrate("de", "fünfzig3".as_bytes()), 53);
+        assert_eq!(calibrate("es", "sieteseis".as_bytes()), 76);
+        let english = Calibrator::words(&Vocabulary::english());
+        assert_eq!(value(&english, "sept"), None);
+        assert!(Vocabulary::builtin("xx").is_none());
+    }
+
+    #[test]
+    fn custom_vocabulary() {
+        // words that are prefixes and suffixes of each other
+        let vocab = Vocabulary::parse("# test\na: 1\nab: 2\n\nb: 3\nbc: 4\n");
+        let vocab = Calibrator::words(&vocab.unwrap());
+        assert_eq!(sum("abc".as_bytes(), &vocab), Ok(24));
+        assert_eq!(sum("xbx".as_bytes(), &vocab), Ok(33));
+
+        let err = Vocabulary::parse("one: 1\nten: 10\n").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "line 2, column 6: expected a digit, found `10`"
+        );
+        let err = Vocabulary::parse("one 1\n").unwrap_err();
+        assert_eq!(err.to_string(), "line 1, column 1: expected `:`");
+        let err = Vocabulary::parse("one: 1\nuno: 1\none: 2\n").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "line 3, column 1: `one` is in the vocabulary twice"
+        );
+    }
+
+    fn values(line: &str) -> Vec<(usize, usize, u64)> {
+        let mut values: Vec<_> = Compounds::new()
+            .tokens(line)
+            .into_iter()
+            .map(|t| (t.start, t.end, t.value))
+            .collect();
+        values.sort();
+        values
+    }
+
+    #[test]
+    fn compound_numbers() {
+        assert_eq!(
+            values("twenty-three"),
+            [(0, 6, 20), (0, 12, 23), (7, 12, 3)]
+        );
+        assert_eq!(values("eleven"), [(0, 6, 11)]);
+        assert_eq!(values("sixty6"), [(0, 3, 6), (0, 5, 60), (5, 6, 6)]);
+        assert!(values("one hundred").contains(&(0, 11, 100)));
+        assert!(values("nine hundred and ninety-nine").contains(&(0, 28, 999)));
+        assert!(values("twohundredtwelve").contains(&(0, 16, 212)));
+        // "zero" can't be a tens' unit, and "hundred" isn't a number alone
+        assert_eq!(values("twenty-zero"), [(0, 6, 20), (7, 11, 0)]);
+        assert_eq!(values("hundred"), []);
+    }
+
+    #[test]
+    fn compound_calibration() {
+        let calibrate = |rule, line| value(&Calibrator::compounds(rule), line);
+        use MultiDigitRule::*;
+        assert_eq!(calibrate(Edges, "twenty-three"), Some(23));
+        assert_eq!(calibrate(Leading, "twenty-three"), Some(22));
+        assert_eq!(calibrate(Trailing, "twenty-three"), Some(33));
+        assert_eq!(calibrate(Concat, "twenty-three"), Some(2323));
+        assert_eq!(calibrate(Edges, "eleven x one hundred"), Some(10));
+        assert_eq!(calibrate(Concat, "eleven x one hundred"), Some(11100));
+        assert_eq!(calibrate(Concat, "zero"), Some(0));
+        assert_eq!(calibrate(Edges, "nothing"), None);
+    }
+
+    #[test]
+    fn ambiguous_overlaps() {
+        let calibrate =
+            |line| value(&Calibrator::compounds(MultiDigitRule::Concat), line);
+        // the longest reading wins at each end
+        assert_eq!(calibrate("eighteen"), Some(1818));
+        assert_eq!(calibrate("seventeenine"), Some(179));
+        // the last token is the one that ends last, even if it overlaps the
+        // first
+        assert_eq!(calibrate("twenty-twone"), Some(221));
+        assert_eq!(calibrate("fiftyfour"), Some(5454));
+        // "ninety-nine" is hiding at the start of "ninety-ninety"
+        assert_eq!(calibrate("ninety-ninety"), Some(9990));
+        // a unit followed by tens isn't a compound
+        assert_eq!(calibrate("onetwenty"), Some(120));
+        assert_eq!(calibrate("threehundredeightwo"), Some(3082));
+    }
+
+    #[test]
+    fn line_reports() {
+        let calibrator = Calibrator::words(&Vocabulary::english());
+        let report = calibrator.line_report(4, "xtwone3x").unwrap();
+        assert_eq!(report.line, 4);
+        assert_eq!(report.value, 23);
+        assert_eq!(
+            report.first,
+            Found {
+                value: 2,
+                source: Source::Word,
+                text: "two".to_string(),
+                column: 2,
+            }
+        );
+        assert_eq!(
+            (report.last.source, report.last.column),
+            (Source::Numeral, 7)
+        );
+        assert_eq!(
+            report.to_string(),
+            "line 4: 23 from first 2 (word `two` at column 2), \
+             last 3 (numeral `3` at column 7)"
+        );
+    }
+
+    #[test]
+    fn lines_without_digits() {
+        let input = "1abc2\nnothing\nthree\nnope\n";
+        let calibrator = Calibrator::words(&Vocabulary::english());
+        let err = calibrator.report(input.as_bytes(), false).unwrap_err();
+        assert_eq!(err.to_string(), "line 2: no digits in `nothing`");
+
+        let report = calibrator.report(input.as_bytes(), true).unwrap();
+        assert_eq!(report.sum(), 12 + 33);
+        assert_eq!(report.skipped, [2, 4]);
+        assert!(report
+            .to_string()
+            .ends_with("skipped 2 line(s) without digits: 2, 4\n"));
+    }
+
+    #[test]
+    #[should_panic(expected = "line 2: no digits in `abc`")]
+    fn part1_rejects_lines_without_digits() {
+        part1(Some("1\nabc\n".to_string()));
+    }
+
+    #[test]
+    fn run_with_options() {
+        let options = Options {
+            skip_missing: true,
+            ..Options::default()
+        };
+        let input = Box::new("a1\nb\n7c\n".as_bytes());
+        assert_eq!(run(1, Some(input), &options), Ok(11 + 77));
+        assert_eq!(run(2, None, &Options::default()), Ok(281));
+    }
+
+    /// Lines like the real input's: letters with a few digits mixed in.
+    fn generated_input(lines: usize) -> String {
+        let mut seed = 0x2023_u64;
+        let mut next = move |n: u64| {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1);
+            (seed >> 33) % n
+        };
+        let mut input = String::new();
+        for _ in 0..lines {
+            let len = 1 + next(40);
+            let digit_at = next(len);
+            for i in 0..len {
+                if i == digit_at || next(8) == 0 {
+                    input.push((b'0' + next(10) as u8) as char);
+                } else {
+                    input.push((b'a' + next(26) as u8) as char);
+                }
+            }
+            input.push('\n');
+        }
+        input
+    }
+
+    #[test]
+    fn fast_path_matches() {
+        let input = generated_input(10_000);
+        let expected = part1_lines(Some(input.clone()));
+        assert_eq!(part1(Some(input.clone())), expected);
+        for block_size in [1, 7, 64, 1000] {
+            let sum = part1_blocks(input.as_bytes(), block_size);
+            assert_eq!(sum, Ok(expected));
+        }
+        assert_eq!(part1(Some("1x\r\n23\r\n4".to_string())), 11 + 23 + 44);
+        assert_eq!(part1(Some(String::new())), 0);
+    }
+
+    #[test]
+    fn fast_path_errors_count_lines_across_blocks() {
+        let err = part1_blocks("1\n2\n3\n4\nabc\n5\n".as_bytes(), 3);
+        assert_eq!(err.unwrap_err().to_string(), "line 5: no digits in `abc`");
+    }
+
+    /// Compares the fast path with the line by line one; run with
+    /// `cargo test --release bench_part1 -- --ignored --nocapture`.
+    #[test]
+    #[ignore]
+    fn bench_part1() {
+        use std::time::Instant;
+
+        let input = generated_input(5_000_000);
+        let start = Instant::now();
+        let slow = part1_lines(Some(input.clone()));
+        let slow_time = start.elapsed();
+        let start = Instant::now();
+        let fast = part1_blocks(input.as_bytes(), BLOCK_SIZE).unwrap();
+        let fast_time = start.elapsed();
+        assert_eq!(fast, slow);
+        println!(
+            "{} MB: line by line {slow_time:?}, fast path {fast_time:?} ({:.1}x)",
+            input.len() >> 20,
+            slow_time.as_secs_f64() / fast_time.as_secs_f64()
+        );
+    }
 }
@@ -1,98 +1,62 @@
+use crate::explain;
+use crate::graph;
+use crate::grid;
+use crate::point::{Direction, Point};
 use itertools::Itertools;
-use std::collections::HashSet;
 
-type Point = (isize, isize);
-
-fn north(x: isize, y: isize) -> Point {
-    (x, y - 1)
-}
-
-fn south(x: isize, y: isize) -> Point {
-    (x, y + 1)
-}
-
-fn east(x: isize, y: isize) -> Point {
-    (x + 1, y)
-}
-
-fn west(x: isize, y: isize) -> Point {
-    (x - 1, y)
+/// The two directions a pipe tile connects, if it is a pipe.
+fn pipe(tile: u8) -> Option<[Direction; 2]> {
+    use Direction::*;
+    match tile {
+        b'|' => Some([North, South]),
+        b'-' => Some([East, West]),
+        b'L' => Some([North, East]),
+        b'J' => Some([North, West]),
+        b'7' => Some([South, West]),
+        b'F' => Some([South, East]),
+        _ => None,
+    }
 }
 
 #[derive(Debug)]
-struct Grid {
-    tiles: Vec<char>,
-    width: usize,
-    height: usize,
+struct Grid<'a> {
+    tiles: grid::View<'a, u8>,
     start: Point,
     start_conns: Vec<Point>,
 }
 
-impl Grid {
-    fn new(input: &String) -> Self {
-        let lines: Vec<_> = input.lines().collect();
-        let height = lines.len();
-        let width = lines[0].len();
-        let mut start = None;
-        let tiles = lines
+impl<'a> Grid<'a> {
+    fn new(input: &'a str) -> Self {
+        let tiles = grid::View::parse_bytes(input);
+        let (x, y) = tiles
             .iter()
-            .enumerate()
-            .map(|(y, &line)| {
-                line.char_indices().for_each(|(x, c)| {
-                    if c == 'S' {
-                        start = Some((x as isize, y as isize));
-                    }
-                });
-                line.chars()
-            })
-            .flatten()
-            .collect();
-        let start = start.expect("no starting S tile found");
+            .find_map(|(pos, &c)| (c == b'S').then_some(pos))
+            .expect("no starting S tile found");
 
         let mut grid = Self {
-            height,
-            width,
             tiles,
-            start,
+            start: Point::new(x as isize, y as isize),
             start_conns: vec![],
         };
         grid.infer_start_conns();
         grid
     }
 
-    fn get(&self, x: isize, y: isize) -> char {
-        self.tiles[y as usize * self.height + x as usize]
+    fn tile(&self, p: Point) -> Option<u8> {
+        self.tiles.get(p.x, p.y).copied()
     }
 
-    fn adjacent(&self, x: isize, y: isize) -> Vec<Point> {
-        vec![north(0, 0), south(0, 0), east(0, 0), west(0, 0)]
+    fn adjacent(&self, p: Point) -> Vec<(Direction, Point)> {
+        Direction::ALL
             .iter()
-            .filter_map(|(dx, dy)| {
-                let nx = x + dx;
-                let ny = y + dy;
-                if nx >= 0
-                    && nx < self.width as isize
-                    && ny >= 0
-                    && ny < self.height as isize
-                {
-                    Some((nx, ny))
-                } else {
-                    None
-                }
-            })
+            .map(|&dir| (dir, p + dir))
+            .filter(|&(_, n)| self.tiles.contains(n.x, n.y))
             .collect_vec()
     }
 
-    fn connections(&self, x: isize, y: isize) -> Option<Vec<Point>> {
-        match self.get(x, y) {
-            '|' => Some(vec![north(x, y), south(x, y)]),
-            '-' => Some(vec![east(x, y), west(x, y)]),
-            'L' => Some(vec![north(x, y), east(x, y)]),
-            'J' => Some(vec![north(x, y), west(x, y)]),
-            '7' => Some(vec![south(x, y), west(x, y)]),
-            'F' => Some(vec![south(x, y), east(x, y)]),
-            _ => None,
-        }
+    fn connections(&self, p: Point) -> Option<Vec<Point>> {
+        let dirs = pipe(self.tile(p)?)?;
+        Some(dirs.iter().map(|&dir| p + dir).collect_vec())
     }
 
     fn infer_start_conns(&mut self) {
@@ -100,14 +64,14 @@ impl Grid {
             return;
         }
         assert_eq!(self.start_conns.len(), 0);
-        for (nx, ny) in self.adjacent(self.start.0, self.start.1) {
-            if let Some(conns) = self.connections(nx, ny) {
-                if conns
-                    .iter()
-                    .any(|(x, y)| *x == self.start.0 && *y == self.start.1)
-                {
-                    self.start_conns.push((nx, ny));
-                }
+        for (dir, n) in self.adjacent(self.start) {
+            // the neighbor has to lead back the way we came
+            let leads_back = self
+                .tile(n)
+                .and_then(pipe)
+                .is_some_and(|dirs| dirs.contains(&dir.turn_around()));
+            if leads_back {
+                self.start_conns.push(n);
             }
         }
         assert_eq!(self.start_conns.len(), 2);
@@ -119,27 +83,25 @@ fn example_input() -> String {
 }
 
 pub fn part1(input: Option<String>) -> u64 {
-    let grid = Grid::new(&input.unwrap_or_else(example_input));
-    let mut visited = HashSet::from([grid.start]);
-    let mut frontier = grid.start_conns.clone();
-    let mut cur = Vec::new();
-    let mut dist = 0;
-    while !frontier.is_empty() {
-        dist += 1;
-        cur.clear();
-        cur.extend(frontier.drain(..));
-        for tile in &cur {
-            visited.insert(*tile);
-            if let Some(conns) = grid.connections(tile.0, tile.1) {
-                for conn in &conns {
-                    if !visited.contains(conn) {
-                        frontier.push(*conn);
-                    }
-                }
+    let input = input.unwrap_or_else(example_input);
+    let grid = Grid::new(&input);
+    let loop_tiles = graph::bfs(
+        [grid.start],
+        |&tile| {
+            if tile == grid.start {
+                grid.start_conns.clone()
+            } else {
+                grid.connections(tile).unwrap_or_default()
             }
-        }
-    }
-    dist
+        },
+        |_| false,
+    );
+    let farthest = *loop_tiles.costs().values().max().unwrap();
+    explain::step(|| {
+        let tiles = loop_tiles.costs().len();
+        format!("the loop has {tiles} tiles, the farthest {farthest} from S")
+    });
+    farthest as u64
 }
 
 #[cfg(test)]
@@ -150,4 +112,10 @@ mod tests {
     fn example_part1() {
         assert_eq!(part1(None), 8);
     }
+
+    #[test]
+    fn non_square_grid() {
+        let input = "......\n.S--7.\n.|..|.\n.L--J.\n".to_string();
+        assert_eq!(part1(Some(input)), 5);
+    }
 }
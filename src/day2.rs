@@ -1,57 +1,365 @@
-use std::collections::HashMap;
+use crate::explain;
+use crate::parse::{self, Span};
+use aoc2023_derive::LineFormat;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io::BufRead;
+use std::str::FromStr;
 
-fn example_input() -> String {
+pub mod infer;
+pub mod query;
+
+pub fn example_input() -> String {
     String::from_utf8_lossy(include_bytes!("day2_example.txt")).to_string()
 }
 
-pub fn part1(input: Option<String>) -> u64 {
-    let input = input.unwrap_or_else(example_input);
-    let max_cubes = HashMap::from([("red", 12), ("green", 13), ("blue", 14)]);
-    let mut sum = 0;
-
-    'next: for line in input.lines() {
-        let game: Vec<_> = line.split(": ").collect();
-        let sets: Vec<_> = game[1].split("; ").collect();
-        for set in sets {
-            let cubes_by_color: Vec<_> = set.split(", ").collect();
-            for cubes in cubes_by_color {
-                let count_color: Vec<_> = cubes.split_whitespace().collect();
-                let count: u64 = count_color[0].parse().unwrap();
-                let color = count_color[1];
-                if count > max_cubes[color] {
-                    continue 'next;
+#[derive(LineFormat)]
+#[line("Game {id}: {draws}")]
+struct GameLine<'a> {
+    id: u64,
+    draws: Span<'a>,
+}
+
+/// Some cubes of one colour, like "3 blue".
+#[derive(LineFormat, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[line("{count} {color}")]
+pub struct Cubes {
+    pub count: u64,
+    pub color: String,
+}
+
+/// One handful of cubes shown from the bag.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Draw {
+    pub cubes: Vec<Cubes>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Game {
+    pub id: u64,
+    pub draws: Vec<Draw>,
+}
+
+impl Game {
+    pub fn parse(line: Span<'_>) -> parse::Result<Self> {
+        let game = GameLine::from_span(line)?;
+        let mut draws = Vec::new();
+        for draw in game.draws.split(";") {
+            let cubes = draw
+                .split(",")
+                .map(|cubes| Cubes::from_span(cubes.trim()))
+                .collect::<parse::Result<_>>()?;
+            draws.push(Draw { cubes });
+        }
+        Ok(Self { id: game.id, draws })
+    }
+
+    /// Every handful of every draw.
+    pub fn cubes(&self) -> impl Iterator<Item = &Cubes> {
+        self.draws.iter().flat_map(|draw| &draw.cubes)
+    }
+
+    /// The fewest cubes of each colour that could have been in the bag.
+    pub fn min_set(&self) -> BTreeMap<&str, u64> {
+        let mut min_set = BTreeMap::new();
+        for cubes in self.cubes() {
+            let min = min_set.entry(cubes.color.as_str()).or_insert(0);
+            *min = cubes.count.max(*min);
+        }
+        min_set
+    }
+
+    /// The product of the fewest cubes of each of the bag's colours (and
+    /// any others the game shows) that the game could have been played with.
+    pub fn power(&self, bag: &Bag) -> u64 {
+        let min_set = self.min_set();
+        if bag.colors().any(|color| !min_set.contains_key(color)) {
+            return 0;
+        }
+        min_set.values().product()
+    }
+
+    /// The first colour the game shows that isn't in `bag`.
+    fn unknown_color(&self, bag: &Bag) -> Option<&str> {
+        self.cubes()
+            .map(|cubes| cubes.color.as_str())
+            .find(|color| bag.count(color).is_none())
+    }
+
+    /// Whether the game could have been played with `bag`.
+    pub fn is_possible(&self, bag: &Bag) -> bool {
+        self.cubes().all(|cubes| {
+            bag.count(&cubes.color).is_some_and(|n| cubes.count <= n)
+        })
+    }
+}
+
+/// How many cubes of each colour are in the bag.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bag {
+    counts: BTreeMap<String, u64>,
+}
+
+impl Default for Bag {
+    /// The puzzle's bag: 12 red, 13 green and 14 blue cubes.
+    fn default() -> Self {
+        Self::parse("12 red, 13 green, 14 blue").unwrap()
+    }
+}
+
+impl Bag {
+    /// Parses cubes like `12 red, 13 green`, separated by commas or lines.
+    pub fn parse(input: &str) -> parse::Result<Self> {
+        Self::from_span(Span::new(input))
+    }
+
+    /// Parses several bags, one per line.
+    pub fn parse_list(input: &str) -> parse::Result<Vec<Self>> {
+        Span::new(input)
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(Self::from_span)
+            .collect()
+    }
+
+    fn from_span(span: Span) -> parse::Result<Self> {
+        let mut counts = BTreeMap::new();
+        for line in span.lines() {
+            for cubes in line.split(",") {
+                let cubes = cubes.trim();
+                if cubes.is_empty() {
+                    continue;
+                }
+                // the template's own errors talk about `{count}`, which
+                // means nothing to someone writing a bag by hand
+                let Cubes { count, color } =
+                    Cubes::from_span(cubes).map_err(|_| {
+                        cubes.error(format!(
+                            "expected a count and a colour, found `{}`",
+                            cubes.as_str()
+                        ))
+                    })?;
+                if counts.insert(color.clone(), count).is_some() {
+                    let message = format!("`{color}` is in the bag twice");
+                    return Err(cubes.error(message));
                 }
             }
         }
-        let id: u64 = game[0]["Game ".len()..].parse().unwrap();
-        sum += id;
+        Ok(Self { counts })
+    }
+
+    pub fn count(&self, color: &str) -> Option<u64> {
+        self.counts.get(color).copied()
+    }
+
+    pub fn colors(&self) -> impl Iterator<Item = &str> {
+        self.counts.keys().map(String::as_str)
+    }
+
+    /// How many cubes there are in all.
+    pub fn size(&self) -> u64 {
+        self.counts.values().sum()
     }
-    sum
 }
 
-pub fn part2(input: Option<String>) -> u64 {
-    let input = input.unwrap_or_else(example_input);
-    let mut power_sum = 0;
-
-    for line in input.lines() {
-        let game: Vec<_> = line.split(": ").collect();
-        let sets: Vec<_> = game[1].split("; ").collect();
-        let mut min_set =
-            HashMap::from([("red", 0), ("green", 0), ("blue", 0)]);
-        for set in sets {
-            let cubes_by_color: Vec<_> = set.split(", ").collect();
-            for cubes in cubes_by_color {
-                let count_color: Vec<_> = cubes.split_whitespace().collect();
-                let count: u64 = count_color[0].parse().unwrap();
-                let color = count_color[1];
-                if count > min_set[color] {
-                    min_set.insert(color, count);
+impl fmt::Display for Bag {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let cubes = self
+            .counts
+            .iter()
+            .map(|(color, count)| format!("{count} {color}"));
+        write!(f, "{}", cubes.collect::<Vec<_>>().join(", "))
+    }
+}
+
+/// What to do with games that show colours the bag doesn't have.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum UnknownColors {
+    /// Such games are impossible, and their colours count towards their
+    /// power.
+    #[default]
+    Impossible,
+    /// Stop with an error.
+    Reject,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    Parse(parse::ParseError),
+    UnknownColor {
+        game: u64,
+        color: String,
+    },
+    /// A query's `what` doesn't fit in an `i64`, for `game` or (for totals)
+    /// over all the games.
+    Overflow {
+        game: Option<u64>,
+        what: String,
+    },
+}
+
+impl From<parse::ParseError> for Error {
+    fn from(err: parse::ParseError) -> Self {
+        Self::Parse(err)
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Parse(err) => write!(f, "{err}"),
+            Self::UnknownColor { game, color } => {
+                write!(f, "game {game}: `{color}` isn't in the bag")
+            }
+            Self::Overflow {
+                game: Some(game),
+                what,
+            } => write!(f, "game {game}: {what} overflows"),
+            Self::Overflow { game: None, what } => {
+                write!(f, "{what} overflows")
+            }
+        }
+    }
+}
+
+/// Day 2's command line options.
+#[derive(Debug, Default, Clone)]
+pub struct Options {
+    pub bag: Bag,
+    pub unknown_colors: UnknownColors,
+}
+
+impl Options {
+    fn check(&self, game: &Game) -> Result<(), Error> {
+        if self.unknown_colors == UnknownColors::Reject {
+            if let Some(color) = game.unknown_color(&self.bag) {
+                return Err(Error::UnknownColor {
+                    game: game.id,
+                    color: color.to_string(),
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Runs `part` with `options`, on the example input if there's no `input`.
+pub fn run(
+    part: u8,
+    input: Option<Box<dyn BufRead>>,
+    options: &Options,
+) -> Result<u64, Error> {
+    match input {
+        Some(input) => answer(part, input, options),
+        None => answer(part, example_input().as_bytes(), options),
+    }
+}
+
+fn answer(
+    part: u8,
+    input: impl BufRead,
+    options: &Options,
+) -> Result<u64, Error> {
+    let mut answer = 0;
+    for game in games(input) {
+        let game = game?;
+        options.check(&game)?;
+        answer += match part {
+            1 => {
+                let possible = game.is_possible(&options.bag);
+                explain::step(|| {
+                    format!("game {}: possible: {possible}", game.id)
+                });
+                if possible {
+                    game.id
+                } else {
+                    0
                 }
             }
+            2 => {
+                let power = game.power(&options.bag);
+                explain::step(|| format!("game {}: power {power}", game.id));
+                power
+            }
+            _ => panic!("day 2 has no part {part}"),
+        };
+    }
+    Ok(answer)
+}
+
+impl FromStr for Game {
+    type Err = parse::ParseError;
+
+    fn from_str(s: &str) -> parse::Result<Self> {
+        Self::parse(Span::new(s))
+    }
+}
+
+impl fmt::Display for Cubes {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}", self.count, self.color)
+    }
+}
+
+impl fmt::Display for Draw {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, cubes) in self.cubes.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{cubes}")?;
         }
-        power_sum += min_set.values().product::<u64>();
+        Ok(())
     }
-    power_sum
+}
+
+/// Writes the game in normal form, with one space after the colon and each
+/// comma and semicolon and none before them, whatever spacing it was parsed
+/// from, so that parsing the result gives the same game back.
+impl fmt::Display for Game {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Game {}: ", self.id)?;
+        for (i, draw) in self.draws.iter().enumerate() {
+            if i > 0 {
+                write!(f, "; ")?;
+            }
+            write!(f, "{draw}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Parses every game in `input`.
+pub fn games(input: impl BufRead) -> impl Iterator<Item = parse::Result<Game>> {
+    input
+        .lines()
+        .enumerate()
+        .map(|(i, line)| Game::parse(Span::at_line(&line.unwrap(), i + 1)))
+}
+
+/// Every game in `input` as a JSON array.
+pub fn to_json(input: impl BufRead) -> parse::Result<String> {
+    let games = games(input).collect::<parse::Result<Vec<_>>>()?;
+    Ok(serde_json::to_string_pretty(&games).unwrap())
+}
+
+pub fn part1(input: Option<String>) -> u64 {
+    let input = input.unwrap_or_else(example_input);
+    part1_stream(input.as_bytes())
+}
+
+pub fn part1_stream(input: impl BufRead) -> u64 {
+    answer(1, input, &Options::default()).unwrap()
+}
+
+pub fn part2(input: Option<String>) -> u64 {
+    let input = input.unwrap_or_else(example_input);
+    part2_stream(input.as_bytes())
+}
+
+pub fn part2_stream(input: impl BufRead) -> u64 {
+    answer(2, input, &Options::default()).unwrap()
 }
 
 #[cfg(test)]
@@ -67,4 +375,107 @@ mod tests {
     fn example_part2() {
         assert_eq!(part2(None), 2286);
     }
+
+    #[test]
+    fn round_trip() {
+        for line in example_input().lines() {
+            let game: Game = line.parse().unwrap();
+            assert_eq!(game.to_string(), line);
+        }
+        let line = "Game 3:  8 green,6 blue ;1 red ,  2 red";
+        let game: Game = line.parse().unwrap();
+        let normal = game.to_string();
+        assert_eq!(normal, "Game 3: 8 green, 6 blue; 1 red, 2 red");
+        assert_eq!(normal.parse::<Game>().unwrap(), game);
+        let game: Game = "Game 7: 1 red".parse().unwrap();
+        assert_eq!(
+            game,
+            Game {
+                id: 7,
+                draws: vec![Draw {
+                    cubes: vec![Cubes {
+                        count: 1,
+                        color: "red".to_string()
+                    }]
+                }]
+            }
+        );
+    }
+
+    #[test]
+    fn parse_errors() {
+        let err = "Game x: 1 red".parse::<Game>().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "line 1, column 6: `id`: expected u64, found `x`"
+        );
+        let err = "Game 1: 1 red; 2; 3 blue".parse::<Game>().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "line 1, column 16: expected ` ` after `{count}`"
+        );
+    }
+
+    #[test]
+    fn json() {
+        let json = to_json(example_input().as_bytes()).unwrap();
+        let games: Vec<Game> = serde_json::from_str(&json).unwrap();
+        assert_eq!(games.len(), 5);
+        assert_eq!(
+            games[0].to_string(),
+            example_input().lines().next().unwrap()
+        );
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value[2]["draws"][0]["cubes"][2]["count"], 20);
+        assert_eq!(value[2]["draws"][0]["cubes"][2]["color"], "red");
+    }
+
+    #[test]
+    fn custom_bags() {
+        let input = "\
+Game 1: 3 purple, 1 red
+Game 2: 2 red; 1 red, 5 purple
+Game 3: 1 red, 1 green, 1 blue";
+        let run =
+            |part, options: &Options| answer(part, input.as_bytes(), options);
+
+        // purple isn't in the puzzle's bag, so only game 3 is possible
+        let options = Options::default();
+        assert_eq!(run(1, &options), Ok(3));
+        // and games 1 and 2 have no green or blue
+        assert_eq!(run(2, &options), Ok(1));
+
+        let options = Options {
+            bag: Bag::parse("4 purple,\n2 red").unwrap(),
+            ..Options::default()
+        };
+        assert_eq!(run(1, &options), Ok(1));
+        assert_eq!(run(2, &options), Ok(3 + 2 * 5));
+
+        let options = Options {
+            unknown_colors: UnknownColors::Reject,
+            ..Options::default()
+        };
+        let err = run(1, &options).unwrap_err();
+        assert_eq!(err.to_string(), "game 1: `purple` isn't in the bag");
+    }
+
+    #[test]
+    fn bag_errors() {
+        let err = Bag::parse("1 red, 2 red").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "line 1, column 8: `red` is in the bag twice"
+        );
+        let err = Bag::parse("1 red\nblue").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "line 2, column 1: expected a count and a colour, found `blue`"
+        );
+        let err = Bag::parse("x red").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "line 1, column 1: expected a count and a colour, found `x red`"
+        );
+    }
 }
@@ -0,0 +1,467 @@
+//! What might have been in day 2's bag, if each draw took its cubes one at a
+//! time and put each back before taking the next (sampling with
+//! replacement). Then the part 2 minimum set isn't a bound at all: a game's
+//! cubes only say what share of the bag each colour is, so likely bags are
+//! given for a chosen number of cubes, with 95% confidence intervals.
+//!
+//! A bag fits a game unless a G-test of the game's cubes against the bag's
+//! shares rejects it. With many games some would be rejected by chance, so
+//! each test is held to `ALPHA` divided by the number of games (the
+//! Bonferroni correction), and a bag that really was used fails any game
+//! only 5% of the time.
+
+use super::query::Table;
+use super::{Bag, Game};
+use crate::parse;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeSet;
+use std::fmt;
+use std::io::BufRead;
+
+/// How many standard deviations the confidence intervals span (for 95%).
+const Z: f64 = 1.96;
+
+/// The chance of rejecting the bag the games were really played with.
+const ALPHA: f64 = 0.05;
+
+/// Slack for comparing floating point shares.
+const EPSILON: f64 = 1e-9;
+
+/// The Wilson score interval for the share of `n` cubes that were some
+/// colour, given that `seen` of them were.
+fn wilson(seen: u64, n: u64) -> (f64, f64) {
+    if n == 0 {
+        return (0.0, 1.0);
+    }
+    let (seen, n) = (seen as f64, n as f64);
+    let share = seen / n;
+    let z2 = Z * Z;
+    let center = share + z2 / (2.0 * n);
+    let spread = Z * (share * (1.0 - share) / n + z2 / (4.0 * n * n)).sqrt();
+    let scale = 1.0 + z2 / n;
+    (
+        ((center - spread) / scale).max(0.0),
+        ((center + spread) / scale).min(1.0),
+    )
+}
+
+/// The number of cubes of each colour in the bag of `size` cubes most
+/// likely to give `seen` cubes of each colour, or `None` if `size` is too
+/// small to hold every colour seen.
+pub fn most_likely(seen: &[u64], size: u64) -> Option<Vec<u64>> {
+    let n: u64 = seen.iter().sum();
+    let needed = seen.iter().filter(|&&seen| seen > 0).count() as u64;
+    if size < needed {
+        return None;
+    }
+    if n == 0 {
+        // every bag is as likely as any other
+        let mut counts = vec![0; seen.len()];
+        if size > 0 {
+            *counts.last_mut()? = size;
+        }
+        return Some(counts);
+    }
+    // the log likelihood (the sum of seen * ln(count)) is best with counts
+    // in proportion to seen, so start from those rounded down, with a cube
+    // for every colour seen; it's concave in each count, so moving cubes
+    // one at a time wherever they help most then finds the best bag
+    let mut counts: Vec<u64> = seen
+        .iter()
+        .map(|&seen| {
+            let share = seen as u128 * size as u128 / n as u128;
+            (share as u64).max((seen > 0) as u64)
+        })
+        .collect();
+    // what one more cube of each colour adds, and one fewer takes away
+    let gain = |counts: &[u64], i: usize| {
+        if seen[i] == 0 {
+            return 0.0;
+        }
+        seen[i] as f64 * (1.0 / counts[i] as f64).ln_1p()
+    };
+    let loss = |counts: &[u64], i: usize| match (seen[i], counts[i]) {
+        (_, 0) | (1.., 1) => f64::INFINITY,
+        (0, _) => 0.0,
+        (seen, count) => seen as f64 * (1.0 / (count - 1) as f64).ln_1p(),
+    };
+    let best = |counts: &[u64]| {
+        (0..counts.len())
+            .max_by(|&a, &b| gain(counts, a).total_cmp(&gain(counts, b)))
+    };
+    let worst = |counts: &[u64]| {
+        (0..counts.len())
+            .min_by(|&a, &b| loss(counts, a).total_cmp(&loss(counts, b)))
+    };
+    let total: u128 = counts.iter().map(|&count| count as u128).sum();
+    // rounding down leaves fewer cubes to add than there are colours, and
+    // giving rare colours their one cube fewer than that to take away
+    for _ in total..size as u128 {
+        let i = best(&counts)?;
+        counts[i] += 1;
+    }
+    for _ in size as u128..total {
+        let i = worst(&counts)?;
+        counts[i] -= 1;
+    }
+    loop {
+        let (to, from) = (best(&counts)?, worst(&counts)?);
+        if gain(&counts, to) <= loss(&counts, from) + EPSILON {
+            return Some(counts);
+        }
+        counts[from] -= 1;
+        counts[to] += 1;
+    }
+}
+
+/// How many cubes of each colour a game showed in all.
+fn seen(game: &Game, colors: &BTreeSet<&str>) -> Vec<u64> {
+    colors
+        .iter()
+        .map(|&color| {
+            game.cubes()
+                .filter(|cubes| cubes.color == color)
+                .map(|cubes| cubes.count)
+                .sum()
+        })
+        .collect()
+}
+
+/// ln Γ(x) for x ≥ 0.5, by the Lanczos approximation.
+fn ln_gamma(x: f64) -> f64 {
+    const G: f64 = 7.0;
+    const COEFFICIENTS: [f64; 9] = [
+        0.999_999_999_999_809_9,
+        676.520_368_121_885_1,
+        -1_259.139_216_722_402_8,
+        771.323_428_777_653_1,
+        -176.615_029_162_140_6,
+        12.507_343_278_686_905,
+        -0.138_571_095_265_720_12,
+        9.984_369_578_019_572e-6,
+        1.505_632_735_149_311_6e-7,
+    ];
+    let x = x - 1.0;
+    let t = x + G + 0.5;
+    let sum = COEFFICIENTS[1..]
+        .iter()
+        .zip(1..)
+        .fold(COEFFICIENTS[0], |sum, (c, i)| sum + c / (x + i as f64));
+    0.5 * (2.0 * std::f64::consts::PI).ln() + (x + 0.5) * t.ln() - t + sum.ln()
+}
+
+/// The regularized upper incomplete gamma function Q(a, x), which gives
+/// the chi-squared distribution's tail: P(χ² ≥ x) with k degrees of freedom
+/// is Q(k / 2, x / 2).
+fn upper_gamma(a: f64, x: f64) -> f64 {
+    if x <= 0.0 {
+        return 1.0;
+    }
+    let scale = (a * x.ln() - x - ln_gamma(a)).exp();
+    if x < a + 1.0 {
+        // a series for the lower function converges quickly here
+        let (mut term, mut sum) = (1.0 / a, 1.0 / a);
+        for n in 1..1000 {
+            term *= x / (a + n as f64);
+            sum += term;
+            if term < sum * 1e-15 {
+                break;
+            }
+        }
+        (1.0 - sum * scale).max(0.0)
+    } else {
+        // a continued fraction for the upper one, by Lentz's method
+        const TINY: f64 = 1e-300;
+        let mut b = x + 1.0 - a;
+        let (mut c, mut d) = (1.0 / TINY, 1.0 / b);
+        let mut fraction = d;
+        for n in 1..1000 {
+            let n = n as f64;
+            let an = -n * (n - a);
+            b += 2.0;
+            d = an * d + b;
+            d = 1.0 / if d.abs() < TINY { TINY } else { d };
+            c = b + an / c;
+            c = if c.abs() < TINY { TINY } else { c };
+            fraction *= c * d;
+            if (c * d - 1.0).abs() < 1e-15 {
+                break;
+            }
+        }
+        fraction * scale
+    }
+}
+
+/// How likely cubes drawn from `bag` are to fit it as badly as the game's
+/// do: the p-value of a G-test of the cubes seen of each colour against
+/// the bag's shares.
+fn p_value(bag: &Bag, game: &Game) -> f64 {
+    let mut colors: BTreeSet<&str> = bag.colors().collect();
+    colors.extend(game.cubes().map(|cubes| cubes.color.as_str()));
+    let seen = seen(game, &colors);
+    let n: u64 = seen.iter().sum();
+    if bag.size() == 0 {
+        return 0.0;
+    }
+    let (mut g, mut shares) = (0.0, 0);
+    for (&color, &seen) in colors.iter().zip(&seen) {
+        let share = bag.count(color).unwrap_or(0) as f64 / bag.size() as f64;
+        if share == 0.0 && seen > 0 {
+            // a colour the bag doesn't have
+            return 0.0;
+        }
+        if share > 0.0 {
+            shares += 1;
+        }
+        if seen > 0 {
+            let seen = seen as f64;
+            g += 2.0 * seen * (seen / (n as f64 * share)).ln();
+        }
+    }
+    if shares < 2 {
+        // every cube is the bag's one colour
+        return 1.0;
+    }
+    upper_gamma((shares - 1) as f64 / 2.0, g / 2.0)
+}
+
+/// Whether the game fits `bag`, as one of `games` tested against it.
+fn is_consistent(bag: &Bag, game: &Game, games: usize) -> bool {
+    p_value(bag, game) >= ALPHA / games as f64
+}
+
+/// Each game's likely bag of `size` cubes, and which of the given bags
+/// every game is consistent with.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Inference {
+    pub size: u64,
+    pub games: Table,
+    pub bags: Table,
+}
+
+impl fmt::Display for Inference {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "most likely bags of {} cubes:", self.size)?;
+        writeln!(f, "{}", self.games)?;
+        write!(f, "{}", self.bags)
+    }
+}
+
+impl Inference {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+}
+
+/// Infers what was in the bag for each game, with bags of `size` cubes
+/// (by default, as many as the first of `bags` has, or if there are none,
+/// the most any one game showed, which has room for every colour seen),
+/// and checks each of `bags` against every game.
+pub fn infer(games: &[Game], bags: &[Bag], size: Option<u64>) -> Inference {
+    let size =
+        size.or_else(|| bags.first().map(Bag::size))
+            .unwrap_or_else(|| {
+                let shown = |game: &Game| game.cubes().map(|c| c.count).sum();
+                games.iter().map(shown).max().unwrap_or(0)
+            });
+    let mut colors: BTreeSet<&str> =
+        bags.iter().flat_map(Bag::colors).collect();
+    colors.extend(
+        games
+            .iter()
+            .flat_map(|game| game.cubes().map(|cubes| cubes.color.as_str())),
+    );
+
+    let columns = ["id", "colour", "seen", "share", "likely", "low", "high"];
+    let mut rows = Vec::new();
+    for game in games {
+        let seen = seen(game, &colors);
+        let n = seen.iter().sum();
+        let likely = most_likely(&seen, size);
+        for (i, (&color, &seen)) in colors.iter().zip(&seen).enumerate() {
+            // the cube counts whose share of the bag is in the interval,
+            // and at least one of a colour that was seen
+            let (low, high) = wilson(seen, n);
+            let low = ((low * size as f64 - EPSILON).ceil() as u64)
+                .max((seen > 0) as u64);
+            let high = (high * size as f64 + EPSILON).floor() as u64;
+            let share = if n == 0 { 0.0 } else { seen as f64 / n as f64 };
+            rows.push(vec![
+                Value::from(game.id),
+                Value::from(color),
+                Value::from(seen),
+                Value::from((share * 1000.0).round() / 1000.0),
+                Value::from(likely.as_ref().map(|counts| counts[i])),
+                Value::from(low),
+                Value::from(high.max(low)),
+            ]);
+        }
+    }
+    let games_table = Table {
+        columns: columns.map(String::from).to_vec(),
+        rows,
+    };
+
+    let bags_table = Table {
+        columns: ["bag", "consistent", "inconsistent games"]
+            .map(String::from)
+            .to_vec(),
+        rows: bags
+            .iter()
+            .map(|bag| {
+                let inconsistent: Vec<_> = games
+                    .iter()
+                    .filter(|game| !is_consistent(bag, game, games.len()))
+                    .map(|game| Value::from(game.id))
+                    .collect();
+                vec![
+                    Value::from(bag.to_string()),
+                    Value::from(inconsistent.is_empty()),
+                    Value::from(inconsistent),
+                ]
+            })
+            .collect(),
+    };
+    Inference {
+        size,
+        games: games_table,
+        bags: bags_table,
+    }
+}
+
+/// Runs [`infer`] over the games in `input`.
+pub fn run(
+    input: impl BufRead,
+    bags: &[Bag],
+    size: Option<u64>,
+) -> parse::Result<Inference> {
+    let games = super::games(input).collect::<parse::Result<Vec<_>>>()?;
+    Ok(infer(&games, bags, size))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// The log likelihood of `seen` from a bag with `counts`.
+    fn likelihood(seen: &[u64], counts: &[u64]) -> f64 {
+        let size: u64 = counts.iter().sum();
+        seen.iter()
+            .zip(counts)
+            .filter(|&(&seen, _)| seen > 0)
+            .map(|(&seen, &count)| {
+                seen as f64 * (count as f64 / size as f64).ln()
+            })
+            .sum()
+    }
+
+    #[test]
+    fn most_likely_matches_brute_force() {
+        for seen in [[9, 4, 5], [1, 0, 7], [3, 3, 3], [0, 0, 2]] {
+            for size in 3..20 {
+                let best = most_likely(&seen, size).unwrap();
+                assert_eq!(best.iter().sum::<u64>(), size);
+                for a in 0..=size {
+                    for b in 0..=size - a {
+                        let counts = [a, b, size - a - b];
+                        assert!(
+                            likelihood(&seen, &counts)
+                                <= likelihood(&seen, &best) + EPSILON,
+                            "{seen:?} in {size}: {counts:?} beats {best:?}"
+                        );
+                    }
+                }
+            }
+        }
+        assert_eq!(most_likely(&[1, 1, 1], 2), None);
+        assert_eq!(most_likely(&[0, 0], 3), Some(vec![0, 3]));
+    }
+
+    #[test]
+    fn most_likely_huge_bags() {
+        let best = most_likely(&[9, 4, 5], 1 << 60).unwrap();
+        assert_eq!(best.iter().sum::<u64>(), 1 << 60);
+        assert_eq!(best[0] / 9, best[1] / 4);
+        // colours seen once still need a cube, even against a billion
+        let best = most_likely(&[1_000_000_000, 1, 1], 3).unwrap();
+        assert_eq!(best, [1, 1, 1]);
+        let best = most_likely(&[1_000_000_000, 1, 1], u64::MAX).unwrap();
+        assert_eq!(best.iter().sum::<u64>(), u64::MAX);
+    }
+
+    #[test]
+    fn wilson_intervals() {
+        let (low, high) = wilson(5, 10);
+        assert!((low - 0.237).abs() < 0.001 && (high - 0.763).abs() < 0.001);
+        assert_eq!(wilson(0, 10).0, 0.0);
+        assert_eq!(wilson(10, 10).1, 1.0);
+    }
+
+    #[test]
+    fn chi_squared_tails() {
+        // with 2 degrees of freedom the tail is exp(-x / 2)
+        for x in [0.5, 2.0, 5.0, 30.0] {
+            let tail = upper_gamma(1.0, x / 2.0);
+            assert!((tail - (-x / 2.0).exp()).abs() < 1e-12, "{x}: {tail}");
+        }
+        // the familiar 5% critical values for 1 and 3 degrees of freedom
+        assert!((upper_gamma(0.5, 3.841 / 2.0) - 0.05).abs() < 1e-4);
+        assert!((upper_gamma(1.5, 7.815 / 2.0) - 0.05).abs() < 1e-4);
+        assert!((ln_gamma(5.0) - 24f64.ln()).abs() < 1e-12);
+    }
+
+    #[test]
+    fn example() {
+        let input = crate::day2::example_input();
+        let bags = Bag::parse_list(
+            "12 red, 13 green, 14 blue\n\n1 red, 1 green, 1 blue\n2 red",
+        )
+        .unwrap();
+        assert_eq!(bags.len(), 3);
+        let inference = run(input.as_bytes(), &bags, None).unwrap();
+        assert_eq!(inference.size, 39);
+
+        // game 1 showed 9 blue, 4 green and 5 red cubes
+        let game1: Vec<_> = inference.games.rows[..3]
+            .iter()
+            .map(|row| (row[1].clone(), row[2].clone(), row[4].clone()))
+            .collect();
+        assert_eq!(
+            game1,
+            [
+                (json!("blue"), json!(9), json!(19)),
+                (json!("green"), json!(4), json!(9)),
+                (json!("red"), json!(5), json!(11))
+            ]
+        );
+
+        // game 4's 7 green out of 51 is too few for a third of the bag even
+        // with five games tested, while game 3's 11 blue out of 62 would
+        // only be too few for one game on its own
+        let consistent: Vec<_> = inference
+            .bags
+            .rows
+            .iter()
+            .map(|row| (row[1].clone(), row[2].clone()))
+            .collect();
+        assert_eq!(
+            consistent,
+            [
+                (json!(false), json!([3, 4])),
+                (json!(false), json!([4])),
+                (json!(false), json!([1, 2, 3, 4, 5]))
+            ]
+        );
+    }
+
+    #[test]
+    fn size_without_bags() {
+        // game 3 showed the most cubes, 62
+        let input = crate::day2::example_input();
+        let inference = run(input.as_bytes(), &[], None).unwrap();
+        assert_eq!(inference.size, 62);
+        assert!(inference.games.rows.iter().all(|row| !row[4].is_null()));
+    }
+}
@@ -0,0 +1,854 @@
+//! A small query language for asking day 2's games questions the puzzle
+//! doesn't, like `sum(id) where power > 500` or
+//! `count by colour where max colour > bag colour`.
+//!
+//! ```text
+//! query := item ("," item)* ["by" "colour"] ["where" cond]
+//! item  := "count" | ("sum" | "max" | "min") "(" expr ")" | expr
+//! cond  := and ("or" and)*
+//! and   := not ("and" not)*
+//! not   := "not" not | expr CMP expr | "(" cond ")"
+//! expr  := term (("+" | "-") term)*
+//! term  := atom ("*" atom)*
+//! atom  := NUMBER | "id" | "power" | "draws"
+//!        | ("max" | "total" | "bag") color | "(" expr ")"
+//! color := "colour" | NAME
+//! ```
+//!
+//! A NAME can be any word but the keywords above, so that `max where` is an
+//! error rather than the most `where` cubes.
+//!
+//! Plain expressions give a row per matching game; `count`, `sum`, `max`
+//! and `min` give one row of totals. `by colour` repeats the query for each
+//! colour in the bag or the games, with `colour` standing for that colour.
+
+use super::{Bag, Error, Game};
+use crate::parse::{self, Span};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeSet;
+use std::fmt;
+use std::io::BufRead;
+use std::str::FromStr;
+
+/// A colour in an expression: a specific one, or each one in turn.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Color {
+    Named(String),
+    Each,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    Add,
+    Sub,
+    Mul,
+}
+
+impl Op {
+    fn symbol(self) -> &'static str {
+        match self {
+            Self::Add => "+",
+            Self::Sub => "-",
+            Self::Mul => "*",
+        }
+    }
+
+    fn precedence(self) -> u8 {
+        match self {
+            Self::Add | Self::Sub => 1,
+            Self::Mul => 2,
+        }
+    }
+}
+
+/// A number worked out from a single game.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expr {
+    Number(i64),
+    Id,
+    Power,
+    /// How many draws the game has.
+    Draws,
+    /// The most cubes of a colour shown in any one draw.
+    Max(Color),
+    /// The cubes of a colour shown over all the draws.
+    Total(Color),
+    /// The cubes of a colour in the bag.
+    Bag(Color),
+    Binary(Box<Expr>, Op, Box<Expr>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cmp {
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Eq,
+    Ne,
+}
+
+impl Cmp {
+    const SYMBOLS: [(&'static str, Cmp); 6] = [
+        ("<=", Cmp::Le),
+        (">=", Cmp::Ge),
+        ("!=", Cmp::Ne),
+        ("<", Cmp::Lt),
+        (">", Cmp::Gt),
+        ("=", Cmp::Eq),
+    ];
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Cond {
+    Compare(Expr, Cmp, Expr),
+    And(Box<Cond>, Box<Cond>),
+    Or(Box<Cond>, Box<Cond>),
+    Not(Box<Cond>),
+}
+
+/// One column of a query's result.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Item {
+    /// A value for each game.
+    Expr(Expr),
+    Count,
+    Sum(Expr),
+    Max(Expr),
+    Min(Expr),
+}
+
+impl Item {
+    fn is_total(&self) -> bool {
+        !matches!(self, Self::Expr(_))
+    }
+
+    fn total(&self, matching: &[Context]) -> Result<Value, Error> {
+        let values = |expr: &Expr| {
+            matching
+                .iter()
+                .map(|cx| expr.value(cx))
+                .collect::<Result<Vec<_>, _>>()
+        };
+        Ok(match self {
+            Self::Count => Value::from(matching.len()),
+            Self::Sum(expr) => {
+                let sum = values(expr)?
+                    .into_iter()
+                    .try_fold(0, i64::checked_add)
+                    .ok_or_else(|| Error::Overflow {
+                        game: None,
+                        what: format!("`{self}`"),
+                    })?;
+                Value::from(sum)
+            }
+            Self::Max(expr) => Value::from(values(expr)?.into_iter().max()),
+            Self::Min(expr) => Value::from(values(expr)?.into_iter().min()),
+            Self::Expr(_) => unreachable!("per-game value mixed with totals"),
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Query {
+    pub items: Vec<Item>,
+    pub by_color: bool,
+    pub filter: Option<Cond>,
+}
+
+/// What an expression is evaluated against.
+#[derive(Clone, Copy)]
+struct Context<'a> {
+    game: &'a Game,
+    bag: &'a Bag,
+    /// The colour `colour` stands for.
+    color: Option<&'a str>,
+}
+
+impl<'a> Context<'a> {
+    fn color(&self, color: &'a Color) -> &'a str {
+        match color {
+            Color::Named(name) => name,
+            Color::Each => self.color.expect("`colour` outside `by colour`"),
+        }
+    }
+}
+
+impl Expr {
+    /// `None` if the value doesn't fit in an `i64`.
+    fn eval(&self, cx: &Context) -> Option<i64> {
+        match self {
+            Self::Number(n) => Some(*n),
+            Self::Id => i64::try_from(cx.game.id).ok(),
+            Self::Power => i64::try_from(cx.game.power(cx.bag)).ok(),
+            Self::Draws => i64::try_from(cx.game.draws.len()).ok(),
+            Self::Max(color) => {
+                let color = cx.color(color);
+                let min_set = cx.game.min_set();
+                i64::try_from(min_set.get(color).copied().unwrap_or(0)).ok()
+            }
+            Self::Total(color) => {
+                let color = cx.color(color);
+                cx.game
+                    .cubes()
+                    .filter(|cubes| cubes.color == color)
+                    .try_fold(0, |total: i64, cubes| {
+                        total.checked_add(i64::try_from(cubes.count).ok()?)
+                    })
+            }
+            Self::Bag(color) => {
+                i64::try_from(cx.bag.count(cx.color(color)).unwrap_or(0)).ok()
+            }
+            Self::Binary(lhs, op, rhs) => {
+                let (lhs, rhs) = (lhs.eval(cx)?, rhs.eval(cx)?);
+                match op {
+                    Op::Add => lhs.checked_add(rhs),
+                    Op::Sub => lhs.checked_sub(rhs),
+                    Op::Mul => lhs.checked_mul(rhs),
+                }
+            }
+        }
+    }
+
+    fn value(&self, cx: &Context) -> Result<i64, Error> {
+        self.eval(cx).ok_or_else(|| Error::Overflow {
+            game: Some(cx.game.id),
+            what: format!("`{self}`"),
+        })
+    }
+}
+
+impl Cond {
+    /// `None` if either side of a comparison overflows.
+    fn eval(&self, cx: &Context) -> Option<bool> {
+        Some(match self {
+            Self::Compare(lhs, cmp, rhs) => {
+                let (lhs, rhs) = (lhs.eval(cx)?, rhs.eval(cx)?);
+                match cmp {
+                    Cmp::Lt => lhs < rhs,
+                    Cmp::Le => lhs <= rhs,
+                    Cmp::Gt => lhs > rhs,
+                    Cmp::Ge => lhs >= rhs,
+                    Cmp::Eq => lhs == rhs,
+                    Cmp::Ne => lhs != rhs,
+                }
+            }
+            Self::And(lhs, rhs) => lhs.eval(cx)? && rhs.eval(cx)?,
+            Self::Or(lhs, rhs) => lhs.eval(cx)? || rhs.eval(cx)?,
+            Self::Not(cond) => !cond.eval(cx)?,
+        })
+    }
+}
+
+/// A query's answer: named columns, and a row of values for each game (or
+/// each colour, for totals).
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Table {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<Value>>,
+}
+
+impl Table {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+}
+
+impl fmt::Display for Table {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let cell = |value: &Value| match value {
+            Value::String(s) => s.clone(),
+            Value::Null => "-".to_string(),
+            Value::Array(values) => values
+                .iter()
+                .map(Value::to_string)
+                .collect::<Vec<_>>()
+                .join(", "),
+            value => value.to_string(),
+        };
+        let mut widths: Vec<_> = self.columns.iter().map(String::len).collect();
+        for row in &self.rows {
+            for (width, value) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell(value).len());
+            }
+        }
+        let mut write_row = |cells: Vec<(String, bool)>| {
+            let line = cells
+                .iter()
+                .zip(&widths)
+                .map(|((text, right), &width)| {
+                    if *right {
+                        format!("{text:>width$}")
+                    } else {
+                        format!("{text:<width$}")
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join("  ");
+            writeln!(f, "{}", line.trim_end())
+        };
+        write_row(self.columns.iter().map(|c| (c.clone(), false)).collect())?;
+        for row in &self.rows {
+            // numbers line up on the right, like in a spreadsheet
+            write_row(
+                row.iter()
+                    .map(|value| (cell(value), !value.is_string()))
+                    .collect(),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl Query {
+    pub fn run(&self, games: &[Game], bag: &Bag) -> Result<Table, Error> {
+        let colors: Vec<Option<&str>> = if self.by_color {
+            let mut colors: BTreeSet<&str> = bag.colors().collect();
+            colors.extend(games.iter().flat_map(|game| {
+                game.cubes().map(|cubes| cubes.color.as_str())
+            }));
+            colors.into_iter().map(Some).collect()
+        } else {
+            vec![None]
+        };
+
+        let mut columns = Vec::new();
+        if self.by_color {
+            columns.push("colour".to_string());
+        }
+        columns.extend(self.items.iter().map(Item::to_string));
+
+        let totals = self.items.iter().any(Item::is_total);
+        let mut rows = Vec::new();
+        for color in colors {
+            let mut matching = Vec::new();
+            for game in games {
+                let cx = Context { game, bag, color };
+                let Some(filter) = &self.filter else {
+                    matching.push(cx);
+                    continue;
+                };
+                let keep = filter.eval(&cx).ok_or_else(|| Error::Overflow {
+                    game: Some(game.id),
+                    what: "the `where` condition".to_string(),
+                })?;
+                if keep {
+                    matching.push(cx);
+                }
+            }
+            let label = color.map(Value::from);
+            if totals {
+                let totals = self
+                    .items
+                    .iter()
+                    .map(|item| item.total(&matching))
+                    .collect::<Result<Vec<_>, _>>()?;
+                rows.push(label.into_iter().chain(totals).collect());
+                continue;
+            }
+            for cx in &matching {
+                let values = self
+                    .items
+                    .iter()
+                    .map(|item| match item {
+                        Item::Expr(expr) => expr.value(cx).map(Value::from),
+                        _ => unreachable!("totals mixed with per-game values"),
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                rows.push(label.clone().into_iter().chain(values).collect());
+            }
+        }
+        Ok(Table { columns, rows })
+    }
+}
+
+/// Runs `query` over the games in `input`.
+pub fn run(
+    query: &str,
+    input: impl BufRead,
+    bag: &Bag,
+) -> Result<Table, Error> {
+    let query: Query = query.parse()?;
+    let games = super::games(input).collect::<parse::Result<Vec<_>>>()?;
+    query.run(&games, bag)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Token<'a> {
+    Word(&'a str),
+    Number(i64),
+    Symbol(&'a str),
+}
+
+impl fmt::Display for Token<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Word(word) => write!(f, "`{word}`"),
+            Self::Number(n) => write!(f, "`{n}`"),
+            Self::Symbol(symbol) => write!(f, "`{symbol}`"),
+        }
+    }
+}
+
+/// Words with a meaning of their own, which can't be colour names.
+const KEYWORDS: [&str; 16] = [
+    "count", "sum", "max", "min", "by", "colour", "color", "where", "or",
+    "and", "not", "id", "power", "draws", "total", "bag",
+];
+
+const SYMBOLS: [&str; 12] = [
+    "<=", ">=", "!=", "<", ">", "=", "(", ")", ",", "+", "-", "*",
+];
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+fn tokenize(query: Span) -> parse::Result<Vec<(Token, Span)>> {
+    let text = query.as_str();
+    let mut tokens = Vec::new();
+    let mut start = 0;
+    loop {
+        let rest = text[start..].trim_start();
+        start = text.len() - rest.len();
+        let Some(c) = rest.chars().next() else {
+            return Ok(tokens);
+        };
+        let len = if is_word_char(c) {
+            rest.find(|c| !is_word_char(c)).unwrap_or(rest.len())
+        } else if let Some(symbol) =
+            SYMBOLS.iter().find(|&&s| rest.starts_with(s))
+        {
+            symbol.len()
+        } else {
+            let span = query.slice(start..start + c.len_utf8());
+            return Err(span.error(format!("unexpected `{c}`")));
+        };
+        let span = query.slice(start..start + len);
+        let word = span.as_str();
+        let token = if !is_word_char(c) {
+            Token::Symbol(word)
+        } else if word.starts_with(|c: char| c.is_ascii_digit()) {
+            Token::Number(span.int()?)
+        } else {
+            Token::Word(word)
+        };
+        tokens.push((token, span));
+        start += len;
+    }
+}
+
+struct Parser<'a> {
+    tokens: Vec<(Token<'a>, Span<'a>)>,
+    pos: usize,
+    end: Span<'a>,
+    /// Where `colour` was first used, so it can be rejected without
+    /// `by colour`.
+    each_color: Option<Span<'a>>,
+}
+
+impl<'a> Parser<'a> {
+    fn peek_at(&self, ahead: usize) -> Option<Token<'a>> {
+        self.tokens.get(self.pos + ahead).map(|&(token, _)| token)
+    }
+
+    fn peek(&self) -> Option<Token<'a>> {
+        self.peek_at(0)
+    }
+
+    /// Where the next token starts, or the end of the query.
+    fn span(&self) -> Span<'a> {
+        self.tokens
+            .get(self.pos)
+            .map_or(self.end, |&(_, span)| span)
+    }
+
+    fn found(&self) -> String {
+        self.peek()
+            .map_or("end of query".to_string(), |token| token.to_string())
+    }
+
+    fn error(&self, expected: &str) -> parse::ParseError {
+        self.span()
+            .error(format!("expected {expected}, found {}", self.found()))
+    }
+
+    fn eat(&mut self, token: Token) -> bool {
+        let matches = self.peek() == Some(token);
+        if matches {
+            self.pos += 1;
+        }
+        matches
+    }
+
+    fn eat_word(&mut self, word: &str) -> bool {
+        self.eat(Token::Word(word))
+    }
+
+    fn expect(&mut self, symbol: &str) -> parse::Result<()> {
+        if self.eat(Token::Symbol(symbol)) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("`{symbol}`")))
+        }
+    }
+
+    fn eat_color_keyword(&mut self) -> bool {
+        self.eat_word("colour") || self.eat_word("color")
+    }
+
+    fn query(&mut self) -> parse::Result<Query> {
+        let mut items = vec![self.item()?];
+        while self.eat(Token::Symbol(",")) {
+            items.push(self.item()?);
+        }
+        let by_color = self.eat_word("by");
+        if by_color && !self.eat_color_keyword() {
+            return Err(self.error("`colour` after `by`"));
+        }
+        let filter = if self.eat_word("where") {
+            Some(self.cond()?)
+        } else {
+            None
+        };
+        if self.peek().is_some() {
+            let message = format!("unexpected {}", self.found());
+            return Err(self.span().error(message));
+        }
+        if let (false, Some(span)) = (by_color, self.each_color) {
+            return Err(span.error("`colour` needs `by colour`"));
+        }
+        Ok(Query {
+            items: items.into_iter().map(|(item, _)| item).collect(),
+            by_color,
+            filter,
+        })
+    }
+
+    fn item(&mut self) -> parse::Result<(Item, Span<'a>)> {
+        let span = self.span();
+        let total = match self.peek() {
+            Some(Token::Word("count")) => {
+                self.pos += 1;
+                return Ok((Item::Count, span));
+            }
+            Some(Token::Word(word @ ("sum" | "max" | "min")))
+                if self.peek_at(1) == Some(Token::Symbol("(")) =>
+            {
+                word
+            }
+            _ => return Ok((Item::Expr(self.expr()?), span)),
+        };
+        self.pos += 2;
+        let expr = self.expr()?;
+        self.expect(")")?;
+        let item = match total {
+            "sum" => Item::Sum(expr),
+            "max" => Item::Max(expr),
+            _ => Item::Min(expr),
+        };
+        Ok((item, span))
+    }
+
+    fn cond(&mut self) -> parse::Result<Cond> {
+        let mut cond = self.and()?;
+        while self.eat_word("or") {
+            cond = Cond::Or(Box::new(cond), Box::new(self.and()?));
+        }
+        Ok(cond)
+    }
+
+    fn and(&mut self) -> parse::Result<Cond> {
+        let mut cond = self.not()?;
+        while self.eat_word("and") {
+            cond = Cond::And(Box::new(cond), Box::new(self.not()?));
+        }
+        Ok(cond)
+    }
+
+    fn not(&mut self) -> parse::Result<Cond> {
+        if self.eat_word("not") {
+            return Ok(Cond::Not(Box::new(self.not()?)));
+        }
+        if self.peek() == Some(Token::Symbol("(")) {
+            // either a parenthesized expression being compared, or a
+            // parenthesized condition: try the first, then fall back
+            let start = self.pos;
+            if let Ok(cond) = self.comparison() {
+                return Ok(cond);
+            }
+            self.pos = start + 1;
+            let cond = self.cond()?;
+            self.expect(")")?;
+            return Ok(cond);
+        }
+        self.comparison()
+    }
+
+    fn comparison(&mut self) -> parse::Result<Cond> {
+        let lhs = self.expr()?;
+        let cmp = Cmp::SYMBOLS
+            .iter()
+            .find(|&&(symbol, _)| self.peek() == Some(Token::Symbol(symbol)))
+            .map(|&(_, cmp)| cmp)
+            .ok_or_else(|| self.error("a comparison like `>`"))?;
+        self.pos += 1;
+        Ok(Cond::Compare(lhs, cmp, self.expr()?))
+    }
+
+    fn binary(
+        &mut self,
+        ops: &[Op],
+        operand: fn(&mut Self) -> parse::Result<Expr>,
+    ) -> parse::Result<Expr> {
+        let mut expr = operand(self)?;
+        while let Some(&op) = ops
+            .iter()
+            .find(|op| self.peek() == Some(Token::Symbol(op.symbol())))
+        {
+            self.pos += 1;
+            expr = Expr::Binary(Box::new(expr), op, Box::new(operand(self)?));
+        }
+        Ok(expr)
+    }
+
+    fn expr(&mut self) -> parse::Result<Expr> {
+        self.binary(&[Op::Add, Op::Sub], Self::term)
+    }
+
+    fn term(&mut self) -> parse::Result<Expr> {
+        self.binary(&[Op::Mul], Self::atom)
+    }
+
+    fn atom(&mut self) -> parse::Result<Expr> {
+        let Some(token) = self.peek() else {
+            return Err(self.error("a value"));
+        };
+        let expr = match token {
+            Token::Number(n) => Expr::Number(n),
+            Token::Word("id") => Expr::Id,
+            Token::Word("power") => Expr::Power,
+            Token::Word("draws") => Expr::Draws,
+            Token::Word(word @ ("max" | "total" | "bag")) => {
+                self.pos += 1;
+                let color = self.color()?;
+                return Ok(match word {
+                    "max" => Expr::Max(color),
+                    "total" => Expr::Total(color),
+                    _ => Expr::Bag(color),
+                });
+            }
+            Token::Symbol("(") => {
+                self.pos += 1;
+                let expr = self.expr()?;
+                self.expect(")")?;
+                return Ok(expr);
+            }
+            _ => return Err(self.error("a value")),
+        };
+        self.pos += 1;
+        Ok(expr)
+    }
+
+    fn color(&mut self) -> parse::Result<Color> {
+        let span = self.span();
+        if self.eat_color_keyword() {
+            self.each_color.get_or_insert(span);
+            return Ok(Color::Each);
+        }
+        match self.peek() {
+            Some(Token::Word(name)) if !KEYWORDS.contains(&name) => {
+                self.pos += 1;
+                Ok(Color::Named(name.to_string()))
+            }
+            _ => Err(self.error("a colour")),
+        }
+    }
+}
+
+impl FromStr for Query {
+    type Err = parse::ParseError;
+
+    fn from_str(s: &str) -> parse::Result<Self> {
+        let span = Span::new(s);
+        let mut parser = Parser {
+            tokens: tokenize(span)?,
+            pos: 0,
+            end: span.end(),
+            each_color: None,
+        };
+        let query = parser.query()?;
+        let totals = query.items.iter().filter(|item| item.is_total()).count();
+        if totals > 0 && totals < query.items.len() {
+            return Err(span.error("can't mix per-game values with totals"));
+        }
+        Ok(query)
+    }
+}
+
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Named(name) => write!(f, "{name}"),
+            Self::Each => write!(f, "colour"),
+        }
+    }
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Number(n) => write!(f, "{n}"),
+            Self::Id => write!(f, "id"),
+            Self::Power => write!(f, "power"),
+            Self::Draws => write!(f, "draws"),
+            Self::Max(color) => write!(f, "max {color}"),
+            Self::Total(color) => write!(f, "total {color}"),
+            Self::Bag(color) => write!(f, "bag {color}"),
+            Self::Binary(lhs, op, rhs) => {
+                // only parenthesize what wouldn't parse back the same way
+                let needs_parens = |expr: &Expr, right: bool| match expr {
+                    Self::Binary(_, inner, _) => {
+                        inner.precedence() < op.precedence()
+                            || (right && inner.precedence() == op.precedence())
+                    }
+                    _ => false,
+                };
+                let operand = |expr: &Expr, right| {
+                    if needs_parens(expr, right) {
+                        format!("({expr})")
+                    } else {
+                        expr.to_string()
+                    }
+                };
+                let (lhs, rhs) = (operand(lhs, false), operand(rhs, true));
+                write!(f, "{lhs} {} {rhs}", op.symbol())
+            }
+        }
+    }
+}
+
+impl fmt::Display for Item {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Expr(expr) => write!(f, "{expr}"),
+            Self::Count => write!(f, "count"),
+            Self::Sum(expr) => write!(f, "sum({expr})"),
+            Self::Max(expr) => write!(f, "max({expr})"),
+            Self::Min(expr) => write!(f, "min({expr})"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn query(query: &str) -> Table {
+        let input = crate::day2::example_input();
+        run(query, input.as_bytes(), &Bag::default()).unwrap()
+    }
+
+    fn column(table: &Table, i: usize) -> Vec<Value> {
+        table.rows.iter().map(|row| row[i].clone()).collect()
+    }
+
+    #[test]
+    fn per_game() {
+        let table = query("id, max red");
+        assert_eq!(table.columns, ["id", "max red"]);
+        assert_eq!(column(&table, 1), [4, 1, 20, 14, 6].map(Value::from));
+
+        let table = query("id where max blue > 10");
+        assert_eq!(table.rows, [[json!(4)]]);
+
+        let table = query("id, total green where not (draws = 3 or id < 3)");
+        assert_eq!(table.rows, [[json!(5), json!(5)]]);
+    }
+
+    #[test]
+    fn totals() {
+        assert_eq!(query("sum(id) where power > 500").rows, [[json!(7)]]);
+        let table = query("count, max(power), min(max red * 2 - 1)");
+        assert_eq!(table.rows, [[json!(5), json!(1560), json!(1)]]);
+        let table = query("max(id) where id > 5");
+        assert_eq!(table.rows, [[Value::Null]]);
+    }
+
+    #[test]
+    fn by_color() {
+        // which colour makes the most games impossible?
+        let table = query("count by colour where max colour > bag colour");
+        assert_eq!(table.columns, ["colour", "count"]);
+        assert_eq!(
+            table.rows,
+            [
+                [json!("blue"), json!(1)],
+                [json!("green"), json!(0)],
+                [json!("red"), json!(2)]
+            ]
+        );
+        assert_eq!(
+            table.to_string(),
+            "colour  count\nblue        1\ngreen       0\nred         2\n"
+        );
+    }
+
+    #[test]
+    fn display_round_trips() {
+        for text in [
+            "max red + 1",
+            "(max red + 1) * 2",
+            "id - (draws - 1)",
+            "bag red - max green",
+        ] {
+            let query: Query = text.parse().unwrap();
+            assert_eq!(query.items[0].to_string(), text);
+        }
+    }
+
+    #[test]
+    fn errors() {
+        let cases = [
+            ("id where", "column 9: expected a value, found end of query"),
+            ("id where id", "column 12: expected a comparison like `>`"),
+            (
+                "id, count",
+                "column 1: can't mix per-game values with totals",
+            ),
+            ("max colour", "column 5: `colour` needs `by colour`"),
+            ("count by red", "column 10: expected `colour` after `by`"),
+            ("sum(id", "column 7: expected `)`, found end of query"),
+            ("id ; 3", "column 4: unexpected `;`"),
+            ("id 3", "column 4: unexpected `3`"),
+            ("max where", "column 5: expected a colour, found `where`"),
+            ("id where max and > 1", "column 14: expected a colour"),
+        ];
+        for (text, expected) in cases {
+            let err = text.parse::<Query>().unwrap_err().to_string();
+            assert!(err.contains(expected), "{text}: {err}");
+        }
+    }
+
+    #[test]
+    fn overflow() {
+        let input = crate::day2::example_input();
+        let bag = Bag::default();
+        let err = |query| run(query, input.as_bytes(), &bag).unwrap_err();
+        assert_eq!(
+            err("id * 9999999999 * 9999999999").to_string(),
+            "game 1: `id * 9999999999 * 9999999999` overflows"
+        );
+        assert_eq!(
+            err("id where id * 9999999999 * 9999999999 > 0").to_string(),
+            "game 1: the `where` condition overflows"
+        );
+        assert_eq!(
+            // each game's value fits, but not their sum
+            err("sum(id * 4611686018427387903) where id < 3").to_string(),
+            "`sum(id * 4611686018427387903)` overflows"
+        );
+    }
+}
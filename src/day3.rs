@@ -1,150 +1,175 @@
-use std::collections::HashSet;
+use crate::explain;
+use crate::grid::{Grid, View};
+use std::ops::Range;
 
 fn example_input() -> String {
     String::from_utf8_lossy(include_bytes!("day3_example.txt")).to_string()
 }
 
-#[derive(Debug)]
-struct Grid {
-    height: usize,
-    width: usize,
-    cells: Vec<char>,
+fn is_symbol(c: u8) -> bool {
+    c != b'.' && !c.is_ascii_digit()
 }
 
-impl Grid {
-    fn new(input: String) -> Self {
-        let lines: Vec<_> = input.lines().collect();
-        Self {
-            height: lines.len(),
-            width: lines[0].len(),
-            cells: lines.iter().map(|&line| line.chars()).flatten().collect(),
-        }
-    }
+/// A number in the schematic, on row `y` across columns `xs`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Number {
+    pub value: u64,
+    pub y: usize,
+    pub xs: Range<usize>,
+}
 
-    fn get(&self, x: usize, y: usize) -> char {
-        self.cells[y * self.height + x]
-    }
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Symbol {
+    pub c: u8,
+    pub x: usize,
+    pub y: usize,
+}
 
-    fn extract_number(&self, x: usize, y: usize) -> (u64, usize, usize) {
-        let mut start_x = x as isize;
-        while start_x >= 0 {
-            let c = self.get(start_x as usize, y);
-            if !c.is_ascii_digit() {
-                break;
+/// Every number and symbol in an engine schematic, and which of them touch,
+/// counting a number once per symbol however many of its digits touch it.
+#[derive(Debug, Clone)]
+pub struct Schematic {
+    pub numbers: Vec<Number>,
+    pub symbols: Vec<Symbol>,
+    /// The symbols each number touches, by index.
+    number_symbols: Vec<Vec<usize>>,
+    /// The numbers each symbol touches, by index.
+    symbol_numbers: Vec<Vec<usize>>,
+}
+
+impl Schematic {
+    pub fn parse(input: &str) -> Self {
+        let grid = View::parse_bytes(input);
+        let mut numbers = Vec::new();
+        let mut symbols = Vec::new();
+        for (y, row) in grid.rows().enumerate() {
+            let mut x = 0;
+            while x < row.len() {
+                if is_symbol(row[x]) {
+                    symbols.push(Symbol { c: row[x], x, y });
+                }
+                if !row[x].is_ascii_digit() {
+                    x += 1;
+                    continue;
+                }
+                let end = row[x..]
+                    .iter()
+                    .position(|c| !c.is_ascii_digit())
+                    .map_or(row.len(), |i| x + i);
+                let value = row[x..end]
+                    .iter()
+                    .fold(0, |num, c| 10 * num + (c - b'0') as u64);
+                numbers.push(Number {
+                    value,
+                    y,
+                    xs: x..end,
+                });
+                x = end;
             }
-            start_x -= 1;
         }
-        start_x += 1;
 
-        let mut end_x = start_x + 1;
-        while end_x < self.width as isize {
-            let c = self.get(end_x as usize, y);
-            if !c.is_ascii_digit() {
-                break;
+        // which number, if any, each digit belongs to
+        let mut owners =
+            Grid::from_fn(grid.width(), grid.height(), |_, _| None);
+        for (i, number) in numbers.iter().enumerate() {
+            for x in number.xs.clone() {
+                owners[(x, number.y)] = Some(i);
             }
-            end_x += 1;
         }
-        let start = y as usize * self.height + start_x as usize;
-        let end = y as usize * self.height + end_x as usize;
-        let mut num = 0;
-        for c in &self.cells[start..end] {
-            num = 10 * num + c.to_digit(10).unwrap() as u64;
+        let mut number_symbols = vec![Vec::new(); numbers.len()];
+        let mut symbol_numbers = Vec::with_capacity(symbols.len());
+        for (s, symbol) in symbols.iter().enumerate() {
+            let mut touching: Vec<usize> = owners
+                .neighbors8(symbol.x, symbol.y)
+                .filter_map(|pos| owners[pos])
+                .collect();
+            touching.sort_unstable();
+            touching.dedup();
+            for &n in &touching {
+                number_symbols[n].push(s);
+            }
+            symbol_numbers.push(touching);
+        }
+        Self {
+            numbers,
+            symbols,
+            number_symbols,
+            symbol_numbers,
         }
-        (num, start_x as usize, end_x as usize)
     }
-}
 
-pub fn part1(input: Option<String>) -> u64 {
-    let grid = Grid::new(input.unwrap_or_else(example_input));
-    let mut digits_seen: HashSet<(usize, usize)> = HashSet::new();
-    let mut sum = 0;
-    for y in 0..grid.height {
-        for x in 0..grid.width {
-            let c = grid.get(x, y);
-            if c == '.' || c.is_ascii_digit() {
-                continue;
-            }
+    /// The symbols touching `self.numbers[number]`.
+    pub fn symbols_touching(
+        &self,
+        number: usize,
+    ) -> impl Iterator<Item = &Symbol> + '_ {
+        self.number_symbols[number]
+            .iter()
+            .map(|&s| &self.symbols[s])
+    }
 
-            // this is a symbol: scan its neighbors for numbers
-            for dy in -1..=1 {
-                let ny = y as isize + dy;
-                if ny < 0 || ny >= grid.height as isize {
-                    continue;
-                }
-                for dx in -1..=1 {
-                    if dx == 0 && dy == 0 {
-                        continue;
-                    }
-                    let nx = x as isize + dx;
-                    if nx < 0 || nx >= grid.width as isize {
-                        continue;
-                    }
-                    if !grid.get(nx as usize, ny as usize).is_ascii_digit()
-                        || digits_seen.contains(&(nx as usize, ny as usize))
-                    {
-                        continue;
-                    }
-
-                    // this is a number we haven't seen before
-                    let (num, start_x, end_x) =
-                        grid.extract_number(nx as usize, ny as usize);
-                    for sx in start_x..end_x {
-                        digits_seen.insert((sx, ny as usize));
-                    }
-                    sum += num
-                }
-            }
-        }
+    /// The numbers touching `self.symbols[symbol]`.
+    pub fn numbers_touching(
+        &self,
+        symbol: usize,
+    ) -> impl Iterator<Item = &Number> + '_ {
+        self.symbol_numbers[symbol]
+            .iter()
+            .map(|&n| &self.numbers[n])
     }
-    sum
-}
 
-pub fn part2(input: Option<String>) -> u64 {
-    let grid = Grid::new(input.unwrap_or_else(example_input));
-    let mut sum = 0;
-    for y in 0..grid.height {
-        for x in 0..grid.width {
-            let c = grid.get(x, y);
-            if c != '*' {
-                continue;
-            }
+    /// Numbers touching at least one symbol, each listed once.
+    pub fn part_numbers(&self) -> impl Iterator<Item = &Number> + '_ {
+        (0..self.numbers.len())
+            .filter(|&n| self.symbols_touching(n).next().is_some())
+            .map(|n| &self.numbers[n])
+    }
 
-            let mut digits_seen: HashSet<(usize, usize)> = HashSet::new();
-            let mut numbers = Vec::new();
-            for dy in -1..=1 {
-                let ny = y as isize + dy;
-                if ny < 0 || ny >= grid.height as isize {
-                    continue;
-                }
-                for dx in -1..=1 {
-                    if dx == 0 && dy == 0 {
-                        continue;
-                    }
-                    let nx = x as isize + dx;
-                    if nx < 0 || nx >= grid.width as isize {
-                        continue;
-                    }
-                    if !grid.get(nx as usize, ny as usize).is_ascii_digit()
-                        || digits_seen.contains(&(nx as usize, ny as usize))
-                    {
-                        continue;
-                    }
-
-                    // this is a number we haven't seen for this gear before
-                    let (num, start_x, end_x) =
-                        grid.extract_number(nx as usize, ny as usize);
-                    numbers.push(num);
-                    for sx in start_x..end_x {
-                        digits_seen.insert((sx, ny as usize));
-                    }
+    /// Each `*` touching exactly two numbers, with those numbers. A number
+    /// between two gears belongs to both.
+    pub fn gears(&self) -> impl Iterator<Item = (&Symbol, [&Number; 2])> + '_ {
+        self.symbols.iter().enumerate().filter_map(|(s, symbol)| {
+            let mut numbers = self.numbers_touching(s);
+            match (numbers.next(), numbers.next(), numbers.next()) {
+                (Some(a), Some(b), None) if symbol.c == b'*' => {
+                    Some((symbol, [a, b]))
                 }
+                _ => None,
             }
-            if numbers.len() == 2 {
-                sum += numbers.iter().product::<u64>()
-            }
-        }
+        })
     }
-    sum
+}
+
+pub fn part1(input: Option<String>) -> u64 {
+    let schematic = Schematic::parse(&input.unwrap_or_else(example_input));
+    schematic
+        .part_numbers()
+        .inspect(|number| {
+            explain::step(|| {
+                let (x, y) = (number.xs.start, number.y);
+                format!("{} at ({x}, {y}) is a part number", number.value)
+            })
+        })
+        .map(|number| number.value)
+        .sum()
+}
+
+pub fn part2(input: Option<String>) -> u64 {
+    let schematic = Schematic::parse(&input.unwrap_or_else(example_input));
+    schematic
+        .gears()
+        .map(|(gear, [a, b])| {
+            let ratio = a.value * b.value;
+            explain::step(|| {
+                let (x, y) = (gear.x, gear.y);
+                format!(
+                    "gear at ({x}, {y}): {} * {} = {ratio}",
+                    a.value, b.value
+                )
+            });
+            ratio
+        })
+        .sum()
 }
 
 #[cfg(test)]
@@ -160,4 +185,63 @@ mod tests {
     fn example_part2() {
         assert_eq!(part2(None), 467835);
     }
+
+    #[test]
+    fn non_square_grid() {
+        let input = "467..114..\n...*......\n..35..633.\n".to_string();
+        assert_eq!(part1(Some(input)), 467 + 35);
+    }
+
+    #[test]
+    fn crlf_input() {
+        let input = example_input().replace('\n', "\r\n");
+        assert_eq!(part1(Some(input.clone())), 4361);
+        assert_eq!(part2(Some(input)), 467835);
+    }
+
+    #[test]
+    fn adjacency() {
+        let schematic = Schematic::parse(&example_input());
+        assert_eq!(schematic.numbers.len(), 10);
+        assert_eq!(schematic.symbols.len(), 6);
+        assert_eq!(
+            schematic.numbers[0],
+            Number {
+                value: 467,
+                y: 0,
+                xs: 0..3
+            }
+        );
+        let touching: Vec<_> =
+            schematic.numbers_touching(0).map(|n| n.value).collect();
+        assert_eq!(touching, [467, 35]);
+        assert_eq!(schematic.symbols_touching(1).count(), 0);
+    }
+
+    #[test]
+    fn numbers_touching_several_symbols() {
+        // 12 touches two `*`s, the second with both its digits, so that
+        // `*` has one number rather than two
+        let input = "\
+*.*.
+.12.
+...5
+2.*5"
+            .to_string();
+        let schematic = Schematic::parse(&input);
+        assert_eq!(schematic.numbers[0].value, 12);
+        let symbols: Vec<_> = schematic
+            .symbols_touching(0)
+            .map(|symbol| (symbol.x, symbol.y))
+            .collect();
+        assert_eq!(symbols, [(0, 0), (2, 0)]);
+        // each part number counts once, however many symbols it touches
+        assert_eq!(part1(Some(input.clone())), 12 + 5 + 5);
+        let gears: Vec<_> = schematic
+            .gears()
+            .map(|(symbol, [a, b])| ((symbol.x, symbol.y), a.value, b.value))
+            .collect();
+        assert_eq!(gears, [((2, 3), 5, 5)]);
+        assert_eq!(part2(Some(input)), 25);
+    }
 }
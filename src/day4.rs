@@ -1,49 +1,64 @@
-use itertools::Itertools;
-use std::collections::{HashMap, HashSet};
+use crate::explain;
+use crate::parse::{self, Span};
+use std::collections::{HashSet, VecDeque};
+use std::io::BufRead;
 
 fn example_input() -> String {
     String::from_utf8_lossy(include_bytes!("day4_example.txt")).to_string()
 }
 
-fn winning_numbers(line: &str) -> HashSet<&str> {
-    let card = line.split(": ").nth(1).unwrap();
-    let (winning, have) = card
-        .split(" | ")
-        .map(|numbers| HashSet::<&str>::from_iter(numbers.split_whitespace()))
-        .collect_tuple()
-        .unwrap();
-    winning.intersection(&have).cloned().collect()
+/// How many of the numbers we have on the card are winning numbers.
+fn winning_numbers(line: Span) -> parse::Result<usize> {
+    let (_, numbers) = line.header()?;
+    let (winning, have) = numbers.split_once("|")?;
+    let winning: HashSet<u32> = winning.ints()?.into_iter().collect();
+    Ok(have.ints()?.iter().filter(|n| winning.contains(n)).count())
 }
 
 pub fn part1(input: Option<String>) -> u64 {
+    part1_stream(input.unwrap_or_else(example_input).as_bytes())
+}
+
+pub fn part1_stream(input: impl BufRead) -> u64 {
     let mut sum = 0;
-    for line in input.unwrap_or_else(example_input).lines() {
-        let won = winning_numbers(line);
-        if won.is_empty() {
+    for (i, line) in input.lines().enumerate() {
+        let line = line.unwrap();
+        let won = winning_numbers(Span::at_line(&line, i + 1)).unwrap();
+        explain::step(|| format!("card {}: {won} winning numbers", i + 1));
+        if won == 0 {
             continue;
         }
-        sum += 2_u64.pow(won.len() as u32 - 1);
+        sum += 2_u64.pow(won as u32 - 1);
     }
     sum
 }
 
 pub fn part2(input: Option<String>) -> u64 {
-    let mut copies_by_card = HashMap::new();
-    for (i, line) in input.unwrap_or_else(example_input).lines().enumerate() {
-        let count = copies_by_card.get(&i).unwrap_or(&0);
-        copies_by_card.insert(i, count + 1);
+    part2_stream(input.unwrap_or_else(example_input).as_bytes())
+}
 
-        let won = winning_numbers(line);
-        if won.is_empty() {
-            continue;
+pub fn part2_stream(input: impl BufRead) -> u64 {
+    // a card can only win copies of the cards right after it, so we only need
+    // to track extra copies for as many upcoming cards as a card can win
+    let mut extra_copies: VecDeque<u64> = VecDeque::new();
+    let mut total = 0;
+    for (i, line) in input.lines().enumerate() {
+        let copies = 1 + extra_copies.pop_front().unwrap_or(0);
+        total += copies;
+
+        let line = line.unwrap();
+        let won = winning_numbers(Span::at_line(&line, i + 1)).unwrap();
+        explain::step(|| {
+            format!("card {}: {copies} copies, each winning {won}", i + 1)
+        });
+        if extra_copies.len() < won {
+            extra_copies.resize(won, 0);
         }
-        let copies = copies_by_card[&i];
-        for j in i + 1..=i + won.len() {
-            let count = copies_by_card.get(&j).unwrap_or(&0);
-            copies_by_card.insert(j, count + copies);
+        for count in extra_copies.iter_mut().take(won) {
+            *count += copies;
         }
     }
-    copies_by_card.values().sum()
+    total
 }
 
 #[cfg(test)]
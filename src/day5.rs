@@ -1,142 +1,163 @@
-use itertools::Itertools;
-use rangemap::RangeMap;
-use rayon::prelude::*;
+use crate::explain;
+use crate::intervals::{IntervalSet, OffsetMap};
+use crate::parse::{self, Span};
+use aoc2023_derive::LineFormat;
 use std::collections::HashMap;
-use std::ops::Range;
-use std::str::Lines;
+
+#[derive(LineFormat)]
+#[line("{source}-to-{dest} map:")]
+struct MapHeader<'a> {
+    source: &'a str,
+    dest: &'a str,
+}
+
+#[derive(LineFormat)]
+#[line("{dest} {source} {len}")]
+struct MapEntry {
+    dest: u64,
+    source: u64,
+    len: u64,
+}
 
 #[derive(Debug)]
-struct CategoryMap {
-    source: String,
-    dest: String,
-    map: RangeMap<u64, Range<u64>>,
+struct CategoryMap<'a> {
+    source: &'a str,
+    dest: &'a str,
+    map: OffsetMap<u64>,
 }
 
-impl CategoryMap {
-    fn new(lines: &mut Lines) -> Option<Self> {
-        let header: Vec<_> = lines.next()?.split_whitespace().collect();
-        assert!(header[1] == "map:");
-        let (source, dest) =
-            header[0].split("-to-").map(String::from).collect_tuple()?;
-
-        let mut map = RangeMap::new();
-        lines.take_while(|line| !line.is_empty()).for_each(|line| {
-            let nums: Vec<_> = line
-                .split_whitespace()
-                .map(|n| n.parse().unwrap())
-                .collect();
-            assert!(nums.len() == 3);
-            map.insert(nums[1]..nums[1] + nums[2], nums[0]..nums[0] + nums[2]);
-        });
-
-        Some(Self { source, dest, map })
+impl<'a> CategoryMap<'a> {
+    fn new(section: Span<'a>) -> parse::Result<Self> {
+        let mut lines = section.lines();
+        let header = lines.next().ok_or_else(|| section.error("empty map"))?;
+        let MapHeader { source, dest } = MapHeader::from_span(header.trim())?;
+
+        let mut map = OffsetMap::new();
+        for line in lines {
+            let MapEntry { dest, source, len } =
+                MapEntry::from_span(line.trim())?;
+            map.insert(source..source + len, dest);
+        }
+
+        Ok(Self { source, dest, map })
     }
 
     fn convert(&self, source: u64) -> u64 {
-        if self.map.contains_key(&source) {
-            let (source_range, dest_range) =
-                self.map.get_key_value(&source).unwrap();
-            dest_range.start + (source - source_range.start)
-        } else {
-            source
-        }
+        self.map.get(source)
     }
 }
 
 #[derive(Debug)]
-struct Almanac {
+struct Almanac<'a> {
     seeds: Vec<u64>,
-    maps: HashMap<(String, String), CategoryMap>,
+    seeds_line: Span<'a>,
+    maps: HashMap<(&'a str, &'a str), CategoryMap<'a>>,
 }
 
-impl Almanac {
-    fn new(input: String) -> Self {
-        let mut lines = input.lines();
+impl<'a> Almanac<'a> {
+    fn new(input: &'a str) -> parse::Result<Self> {
+        let input = Span::new(input);
+        let mut sections = input.sections();
 
-        let seeds = lines
+        let seeds_line = sections
             .next()
-            .unwrap()
-            .split(": ")
-            .nth(1)
-            .unwrap()
-            .split_whitespace()
-            .map(|n| n.parse().unwrap())
-            .collect();
-        lines.next();
+            .ok_or_else(|| input.error("empty almanac"))?
+            .labeled("seeds")?;
+        let seeds = seeds_line.ints()?;
 
         let mut maps = HashMap::new();
-        while let Some(map) = CategoryMap::new(&mut lines) {
-            maps.insert((map.source.clone(), map.dest.clone()), map);
+        for section in sections {
+            let map = CategoryMap::new(section)?;
+            maps.insert((map.source, map.dest), map);
         }
 
-        Self { seeds, maps }
+        Ok(Self {
+            seeds,
+            seeds_line,
+            maps,
+        })
     }
 
-    fn convert(&self, dest: &str, source: &str, value: u64) -> u64 {
+    /// The seeds as part 2 reads them: pairs of a range's start and length.
+    fn seed_ranges(&self) -> parse::Result<IntervalSet<u64>> {
+        let pairs = self.seeds.chunks_exact(2);
+        if !pairs.remainder().is_empty() {
+            return Err(self.seeds_line.error(format!(
+                "expected pairs of start and length, found {} numbers",
+                self.seeds.len()
+            )));
+        }
+        Ok(pairs.map(|pair| pair[0]..pair[0] + pair[1]).collect())
+    }
+
+    fn map(&self, source: &'a str, dest: &'a str) -> &CategoryMap<'a> {
         self.maps
-            .get(&(source.to_string(), dest.to_string()))
-            .expect("unknown map: {source}-to-{dest}")
-            .convert(value)
+            .get(&(source, dest))
+            .unwrap_or_else(|| panic!("unknown map: {source}-to-{dest}"))
     }
 
     fn seed_to_location(&self, seed: u64) -> u64 {
-        // XXX this chain would be nicer if implemented as a builder pattern...
-        self.convert(
-            "location",
-            "humidity",
-            self.convert(
-                "humidity",
-                "temperature",
-                self.convert(
-                    "temperature",
-                    "light",
-                    self.convert(
-                        "light",
-                        "water",
-                        self.convert(
-                            "water",
-                            "fertilizer",
-                            self.convert(
-                                "fertilizer",
-                                "soil",
-                                self.convert("soil", "seed", seed),
-                            ),
-                        ),
-                    ),
-                ),
-            ),
-        )
+        CATEGORIES
+            .windows(2)
+            .fold(seed, |value, w| self.map(w[0], w[1]).convert(value))
+    }
+
+    fn seeds_to_locations(&self, seeds: IntervalSet<u64>) -> IntervalSet<u64> {
+        CATEGORIES
+            .windows(2)
+            .fold(seeds, |values, w| self.map(w[0], w[1]).map.image(&values))
     }
 }
 
+/// The order the almanac's maps convert through.
+const CATEGORIES: [&str; 8] = [
+    "seed",
+    "soil",
+    "fertilizer",
+    "water",
+    "light",
+    "temperature",
+    "humidity",
+    "location",
+];
+
 fn example_input() -> String {
     String::from_utf8_lossy(include_bytes!("day5_example.txt")).to_string()
 }
 
 pub fn part1(input: Option<String>) -> u64 {
-    let almanac = Almanac::new(input.unwrap_or_else(example_input));
+    let input = input.unwrap_or_else(example_input);
+    let almanac = Almanac::new(&input).unwrap();
     almanac
         .seeds
         .iter()
-        .map(|&seed| almanac.seed_to_location(seed))
+        .map(|&seed| {
+            let location = almanac.seed_to_location(seed);
+            explain::step(|| format!("seed {seed} -> location {location}"));
+            location
+        })
         .min()
         .unwrap()
 }
 
+/// Part 1 on ranges of one seed each, through the interval maps part 2
+/// uses, as an independent check on [`part1`].
+pub fn part1_ranges(input: Option<String>) -> u64 {
+    let input = input.unwrap_or_else(example_input);
+    let almanac = Almanac::new(&input).unwrap();
+    let seeds = almanac.seeds.iter().map(|&seed| seed..seed + 1).collect();
+    almanac.seeds_to_locations(seeds).min().unwrap()
+}
+
 pub fn part2(input: Option<String>) -> u64 {
-    let almanac = Almanac::new(input.unwrap_or_else(example_input));
-    let mut new_seeds = Vec::new();
-    almanac
-        .seeds
-        .iter()
-        .step_by(2)
-        .zip(almanac.seeds.iter().skip(1).step_by(2))
-        .for_each(|(&start, &length)| new_seeds.extend(start..start + length));
-    new_seeds
-        .par_iter()
-        .map(|&seed| almanac.seed_to_location(seed))
-        .min()
-        .unwrap()
+    let input = input.unwrap_or_else(example_input);
+    let almanac = Almanac::new(&input).unwrap();
+    let seeds = almanac.seed_ranges().unwrap();
+    let locations = almanac.seeds_to_locations(seeds);
+    for range in locations.ranges() {
+        explain::step(|| format!("seeds reach locations {range:?}"));
+    }
+    locations.min().unwrap()
 }
 
 #[cfg(test)]
@@ -148,6 +169,14 @@ mod tests {
         assert_eq!(part1(None), 35);
     }
 
+    #[test]
+    fn ranges_agree_with_part1() {
+        assert_eq!(part1_ranges(None), 35);
+        // seeds on the edges of the map's ranges, and one past every range
+        let input = example_input().replacen("79 14 55 13", "97 98 99 100", 1);
+        assert_eq!(part1_ranges(Some(input.clone())), part1(Some(input)));
+    }
+
     #[test]
     fn example_part2() {
         assert_eq!(part2(None), 46);
@@ -155,12 +184,12 @@ mod tests {
 
     #[test]
     fn seed_to_soil() {
-        let input = vec!["seed-to-soil map:", "50 98 2", "52 50 48"].join("\n");
-        let mut lines = input.lines();
-        let maybe_map = CategoryMap::new(&mut lines);
-        assert!(maybe_map.is_some());
+        let input = ["seed-to-soil map:", "50 98 2", "52 50 48"].join("\n");
+        let maybe_map = CategoryMap::new(Span::new(&input));
+        assert!(maybe_map.is_ok());
 
         let map = maybe_map.unwrap();
+        assert_eq!((map.source, map.dest), ("seed", "soil"));
         assert_eq!(map.convert(0), 0);
         assert_eq!(map.convert(1), 1);
         assert_eq!(map.convert(50), 52);
@@ -168,4 +197,42 @@ mod tests {
         assert_eq!(map.convert(98), 50);
         assert_eq!(map.convert(99), 51);
     }
+
+    #[test]
+    fn map_entry() {
+        let entry: MapEntry = " 50 98  2".trim().parse().unwrap();
+        assert_eq!((entry.dest, entry.source, entry.len), (50, 98, 2));
+        assert!("50 98 2 7".parse::<MapEntry>().is_err());
+    }
+
+    #[test]
+    fn parse_errors() {
+        let input = "seeds: 1 2\n\nseed-to-soil map:\n50 98\n";
+        let err = Almanac::new(input).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "line 4, column 4: expected ` ` after `{source}`"
+        );
+
+        let input = "seeds: 1 2\n\nseed-to-soil map:\n50 x 2\n";
+        let err = Almanac::new(input).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "line 4, column 4: `source`: expected u64, found `x`"
+        );
+
+        let input = "seeds: 1 2\n\nseed-to-soil:\n50 98 2\n";
+        let err = Almanac::new(input).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "line 3, column 9: expected ` map:` after `{dest}`"
+        );
+
+        let input = "seeds: 1 2 3\n\nseed-to-soil map:\n50 98 2\n";
+        let err = Almanac::new(input).unwrap().seed_ranges().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "line 1, column 8: expected pairs of start and length, found 3 numbers"
+        );
+    }
 }
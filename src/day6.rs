@@ -1,27 +1,30 @@
+use crate::explain;
+use crate::parse::{self, Span};
+
 fn example_input() -> String {
     String::from_utf8_lossy(include_bytes!("day6_example.txt")).to_string()
 }
 
-fn parse_part1(input: String) -> Vec<(u64, u64)> {
-    let lines: Vec<_> = input.lines().collect();
-    assert_eq!(lines.len(), 2);
-
-    let mut time_line = lines[0].split(":");
-    assert_eq!(time_line.next(), Some("Time"));
-    let mut distance_line = lines[1].split(":");
-    assert_eq!(distance_line.next(), Some("Distance"));
+/// Returns the `Time` and `Distance` lines' values.
+fn parse_lines(input: &str) -> parse::Result<(Span<'_>, Span<'_>)> {
+    let input = Span::new(input);
+    let mut lines = input.lines();
+    let mut next_line = || {
+        lines
+            .next()
+            .ok_or_else(|| input.error("expected `Time` and `Distance` lines"))
+    };
+    let times = next_line()?.labeled("Time")?;
+    let distances = next_line()?.labeled("Distance")?;
+    Ok((times, distances))
+}
 
-    let times = time_line
-        .next()
-        .unwrap()
-        .split_whitespace()
-        .map(|n| n.parse().unwrap());
-    let distances = distance_line
-        .next()
-        .unwrap()
-        .split_whitespace()
-        .map(|n| n.parse().unwrap());
-    Vec::from_iter(times.zip(distances))
+fn parse_part1(input: String) -> Vec<(u64, u64)> {
+    let (times, distances) = parse_lines(&input).unwrap();
+    let times = times.ints().unwrap();
+    let distances = distances.ints().unwrap();
+    assert_eq!(times.len(), distances.len());
+    Vec::from_iter(times.into_iter().zip(distances))
 }
 
 fn possible_wins(race: &(u64, u64)) -> u64 {
@@ -44,6 +47,9 @@ fn possible_wins(race: &(u64, u64)) -> u64 {
             break;
         }
     }
+    explain::step(|| {
+        format!("{time} ms race, {record} mm record: hold {lo}..={hi} ms")
+    });
     hi - lo + 1
 }
 
@@ -54,23 +60,17 @@ pub fn part1(input: Option<String>) -> u64 {
         .product()
 }
 
-fn parse_part2(input: String) -> (u64, u64) {
-    let lines: Vec<_> = input.lines().collect();
-    assert_eq!(lines.len(), 2);
-
-    let mut time_line = lines[0].split(":");
-    assert_eq!(time_line.next(), Some("Time"));
-    let mut distance_line = lines[1].split(":");
-    assert_eq!(distance_line.next(), Some("Distance"));
-
-    let time = time_line.next().unwrap().replace(" ", "").parse().unwrap();
-    let distance = distance_line
-        .next()
-        .unwrap()
-        .replace(" ", "")
+/// Parses a number whose digits are spread out over the span.
+fn kerned_int(span: Span) -> parse::Result<u64> {
+    let digits: String = span.words().map(|word| word.as_str()).collect();
+    digits
         .parse()
-        .unwrap();
-    (time, distance)
+        .map_err(|_| span.error(format!("expected a number, found `{digits}`")))
+}
+
+fn parse_part2(input: String) -> (u64, u64) {
+    let (time, distance) = parse_lines(&input).unwrap();
+    (kerned_int(time).unwrap(), kerned_int(distance).unwrap())
 }
 
 pub fn part2(input: Option<String>) -> u64 {
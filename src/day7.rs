@@ -1,10 +1,16 @@
+use crate::explain;
 use itertools::Itertools;
 use std::cmp::Ordering;
+use std::io::BufRead;
 
 fn example_input() -> String {
     String::from_utf8_lossy(include_bytes!("day7_example.txt")).to_string()
 }
 
+/// A hand's cards, kept inline: lines are streamed, so a hand can't borrow
+/// from its line.
+type Hand = [u8; 5];
+
 // from weakest to strongest
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
 enum HandType {
@@ -18,9 +24,9 @@ enum HandType {
 }
 
 impl HandType {
-    fn from_with_jokers(hand: &Vec<char>) -> HandType {
+    fn from_with_jokers(hand: &Hand) -> HandType {
         let mut counts = hand.iter().counts();
-        let jokers = counts.remove(&'J');
+        let jokers = counts.remove(&b'J');
         if let Some(jokers) = jokers {
             if jokers == 5 {
                 HandType::FiveOfAKind
@@ -41,10 +47,10 @@ impl HandType {
                     })
                     .unwrap()
                     .0;
-                let new_hand = hand
-                    .iter()
-                    .map(|&card| if card == 'J' { substitute } else { card })
-                    .collect_vec();
+                let new_hand = hand.map(|card| match card {
+                    b'J' => substitute,
+                    card => card,
+                });
                 HandType::from(&new_hand)
             }
         } else {
@@ -53,9 +59,8 @@ impl HandType {
     }
 }
 
-impl From<&Vec<char>> for HandType {
-    fn from(hand: &Vec<char>) -> HandType {
-        assert_eq!(hand.len(), 5);
+impl From<&Hand> for HandType {
+    fn from(hand: &Hand) -> HandType {
         let counts = hand.iter().counts();
         let max = *counts.values().max().unwrap();
         if counts.len() == 1 {
@@ -82,43 +87,45 @@ impl From<&Vec<char>> for HandType {
     }
 }
 
-impl From<&str> for HandType {
-    fn from(hand: &str) -> HandType {
-        Self::from(&hand.chars().collect_vec())
-    }
-}
-
-fn card_weight(card: char, jokers: bool) -> u32 {
+fn card_weight(card: u8, jokers: bool) -> u32 {
     match card {
-        'A' => 14,
-        'K' => 13,
-        'Q' => 12,
-        'J' => {
+        b'A' => 14,
+        b'K' => 13,
+        b'Q' => 12,
+        b'J' => {
             if jokers {
                 1
             } else {
                 11
             }
         }
-        'T' => 10,
-        '2'..='9' => card.to_digit(10).unwrap(),
-        _ => panic!("unexpected card value {card}"),
+        b'T' => 10,
+        b'2'..=b'9' => (card - b'0') as u32,
+        _ => panic!("unexpected card value {}", card as char),
     }
 }
 
-fn total_winnings(input: &str, jokers: bool) -> u64 {
-    input
-        .lines()
-        .map(str::split_whitespace)
-        .filter_map(|e| e.collect_tuple::<(&str, &str)>())
+/// Each hand and its bid, skipping blank lines.
+fn hands(input: impl BufRead) -> impl Iterator<Item = (Hand, usize)> {
+    input.lines().map(Result::unwrap).filter_map(|line| {
+        let (hand, bid) = line.split_whitespace().collect_tuple()?;
+        let hand = Hand::try_from(hand.as_bytes())
+            .unwrap_or_else(|_| panic!("{hand} is not 5 cards"));
+        Some((hand, bid.parse::<usize>().unwrap()))
+    })
+}
+
+/// Only the parsed hands are kept in memory, since ranking them needs all of
+/// them at once.
+fn total_winnings(input: impl BufRead, jokers: bool) -> u64 {
+    hands(input)
         .map(|(hand, bid)| {
-            let hand = hand.chars().collect_vec();
             let hand_type = if jokers {
                 HandType::from_with_jokers(&hand)
             } else {
                 HandType::from(&hand)
             };
-            (hand_type, hand, bid.parse::<usize>().unwrap())
+            (hand_type, hand, bid)
         })
         .sorted_by(|a, b| {
             let (hand_type_a, hand_a, _) = a;
@@ -138,19 +145,29 @@ fn total_winnings(input: &str, jokers: bool) -> u64 {
         })
         .enumerate()
         .fold(0, |acc, rank_hand_bid| {
-            let (rank, (_, _, bid)) = rank_hand_bid;
+            let (rank, (hand_type, hand, bid)) = rank_hand_bid;
+            explain::step(|| {
+                let hand = String::from_utf8_lossy(&hand);
+                format!("rank {}: {hand} ({hand_type:?}), bid {bid}", rank + 1)
+            });
             acc + (bid * (rank + 1)) as u64
         })
 }
 
 pub fn part1(input: Option<String>) -> u64 {
-    let input = input.unwrap_or_else(example_input);
-    total_winnings(&input, false)
+    part1_stream(input.unwrap_or_else(example_input).as_bytes())
+}
+
+pub fn part1_stream(input: impl BufRead) -> u64 {
+    total_winnings(input, false)
 }
 
 pub fn part2(input: Option<String>) -> u64 {
-    let input = input.unwrap_or_else(example_input);
-    total_winnings(&input, true)
+    part2_stream(input.unwrap_or_else(example_input).as_bytes())
+}
+
+pub fn part2_stream(input: impl BufRead) -> u64 {
+    total_winnings(input, true)
 }
 
 #[cfg(test)]
@@ -159,75 +176,45 @@ mod tests {
 
     #[test]
     fn test_card_weight() {
-        assert_eq!(card_weight('2', false), 2);
-        assert_eq!(card_weight('9', false), 9);
-        assert_eq!(card_weight('T', false), 10);
-        assert_eq!(card_weight('A', false), 14);
-        assert_eq!(card_weight('J', false), 11);
-        assert_eq!(card_weight('J', true), 1);
+        assert_eq!(card_weight(b'2', false), 2);
+        assert_eq!(card_weight(b'9', false), 9);
+        assert_eq!(card_weight(b'T', false), 10);
+        assert_eq!(card_weight(b'A', false), 14);
+        assert_eq!(card_weight(b'J', false), 11);
+        assert_eq!(card_weight(b'J', true), 1);
     }
 
     #[test]
     fn test_hand_type() {
-        assert_eq!(HandType::from("AAAAA"), HandType::FiveOfAKind);
-        assert_eq!(HandType::from("AA8AA"), HandType::FourOfAKind);
-        assert_eq!(HandType::from("23332"), HandType::FullHouse);
-        assert_eq!(HandType::from("TTT98"), HandType::ThreeOfAKind);
-        assert_eq!(HandType::from("23432"), HandType::TwoPair);
-        assert_eq!(HandType::from("A23A4"), HandType::OnePair);
-        assert_eq!(HandType::from("23456"), HandType::HighCard);
-        assert_eq!(
-            HandType::from_with_jokers(&"JJJJJ".chars().collect_vec()),
-            HandType::FiveOfAKind
-        );
-        assert_eq!(
-            HandType::from_with_jokers(&"KJJJJ".chars().collect_vec()),
-            HandType::FiveOfAKind
-        );
-        assert_eq!(
-            HandType::from_with_jokers(&"TTJJJ".chars().collect_vec()),
-            HandType::FiveOfAKind
-        );
+        assert_eq!(HandType::from(b"AAAAA"), HandType::FiveOfAKind);
+        assert_eq!(HandType::from(b"AA8AA"), HandType::FourOfAKind);
+        assert_eq!(HandType::from(b"23332"), HandType::FullHouse);
+        assert_eq!(HandType::from(b"TTT98"), HandType::ThreeOfAKind);
+        assert_eq!(HandType::from(b"23432"), HandType::TwoPair);
+        assert_eq!(HandType::from(b"A23A4"), HandType::OnePair);
+        assert_eq!(HandType::from(b"23456"), HandType::HighCard);
+        assert_eq!(HandType::from_with_jokers(b"JJJJJ"), HandType::FiveOfAKind);
+        assert_eq!(HandType::from_with_jokers(b"KJJJJ"), HandType::FiveOfAKind);
+        assert_eq!(HandType::from_with_jokers(b"TTJJJ"), HandType::FiveOfAKind);
+        assert_eq!(HandType::from_with_jokers(b"KTJJJ"), HandType::FourOfAKind);
+        assert_eq!(HandType::from_with_jokers(b"KTJJT"), HandType::FourOfAKind);
         assert_eq!(
-            HandType::from_with_jokers(&"KTJJJ".chars().collect_vec()),
-            HandType::FourOfAKind
-        );
-        assert_eq!(
-            HandType::from_with_jokers(&"KTJJT".chars().collect_vec()),
-            HandType::FourOfAKind
-        );
-        assert_eq!(
-            HandType::from_with_jokers(&"KTJJ9".chars().collect_vec()),
+            HandType::from_with_jokers(b"KTJJ9"),
             HandType::ThreeOfAKind
         );
+        assert_eq!(HandType::from_with_jokers(b"T55J5"), HandType::FourOfAKind);
+        assert_eq!(HandType::from_with_jokers(b"QQQJA"), HandType::FourOfAKind);
+        assert_eq!(HandType::from_with_jokers(b"5234J"), HandType::OnePair);
+        assert_eq!(HandType::from_with_jokers(b"5333J"), HandType::FourOfAKind);
         assert_eq!(
-            HandType::from_with_jokers(&"T55J5".chars().collect_vec()),
-            HandType::FourOfAKind
-        );
-        assert_eq!(
-            HandType::from_with_jokers(&"QQQJA".chars().collect_vec()),
-            HandType::FourOfAKind
-        );
-        assert_eq!(
-            HandType::from_with_jokers(&"5234J".chars().collect_vec()),
-            HandType::OnePair
-        );
-        assert_eq!(
-            HandType::from_with_jokers(&"5333J".chars().collect_vec()),
-            HandType::FourOfAKind
-        );
-        assert_eq!(
-            HandType::from_with_jokers(&"5233J".chars().collect_vec()),
+            HandType::from_with_jokers(b"5233J"),
             HandType::ThreeOfAKind
         );
         assert_eq!(
-            HandType::from_with_jokers(&"5322J".chars().collect_vec()),
+            HandType::from_with_jokers(b"5322J"),
             HandType::ThreeOfAKind
         );
-        assert_eq!(
-            HandType::from_with_jokers(&"23J32".chars().collect_vec()),
-            HandType::FullHouse
-        );
+        assert_eq!(HandType::from_with_jokers(b"23J32"), HandType::FullHouse);
     }
 
     #[test]
@@ -239,4 +226,38 @@ mod tests {
     fn example_part2() {
         assert_eq!(part2(None), 5905);
     }
+
+    /// Compares keeping each hand as a `Vec<char>`, copied out of a `String`
+    /// per field as this day used to, with keeping it inline; run with
+    /// `cargo test --release bench_hands -- --ignored --nocapture
+    /// --test-threads 1`.
+    #[test]
+    #[ignore]
+    fn bench_hands() {
+        use crate::alloc;
+
+        let input = "32T3K 765\nT55J5 684\nKK677 28\n".repeat(300_000);
+        let (owned, owned_usage) = alloc::measure(|| {
+            input
+                .as_bytes()
+                .lines()
+                .map(Result::unwrap)
+                .filter_map(|line| {
+                    line.split_whitespace()
+                        .map(str::to_string)
+                        .collect_tuple::<(String, String)>()
+                })
+                .map(|(hand, bid)| {
+                    (hand.chars().collect_vec(), bid.parse::<usize>().unwrap())
+                })
+                .collect_vec()
+        });
+        let (inline, inline_usage) =
+            alloc::measure(|| hands(input.as_bytes()).collect_vec());
+        assert_eq!(owned.len(), inline.len());
+        println!(
+            "{} hands: Vec<char> {owned_usage}; inline {inline_usage}",
+            inline.len()
+        );
+    }
 }
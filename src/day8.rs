@@ -1,5 +1,9 @@
+use crate::cycle::{self, Cycle};
+use crate::explain;
+use crate::math;
+use crate::parse::{self, Span};
+use aoc2023_derive::LineFormat;
 use itertools::Itertools;
-use regex::Regex;
 use std::collections::HashMap;
 
 fn part1_example_input() -> String {
@@ -12,109 +16,136 @@ fn part2_example_input() -> String {
         .to_string()
 }
 
-fn gcd(a: u64, b: u64) -> u64 {
-    // the euclidean algorithm
-    if b == 0 {
-        a
-    } else {
-        gcd(b, a % b)
-    }
-}
-
-fn lcm(nums: &[u64]) -> u64 {
-    assert!(!nums.is_empty());
-    if nums.len() == 1 {
-        return nums[0];
-    }
-    let a = nums[0];
-    let b = lcm(&nums[1..]);
-    a * b / gcd(a, b)
+#[derive(LineFormat)]
+#[line("{name} = ({left}, {right})")]
+struct Node<'a> {
+    name: &'a str,
+    left: &'a str,
+    right: &'a str,
 }
 
-struct Network {
-    instrs: Vec<char>,
-    nodes: HashMap<String, (String, String)>,
+struct Network<'a> {
+    instrs: &'a [u8],
+    nodes: HashMap<&'a str, (&'a str, &'a str)>,
 }
 
-impl Network {
-    fn new(input: String) -> Self {
-        let mut lines = input.lines();
-        let instrs = lines.next().unwrap().chars().collect_vec();
-        let mut nodes: HashMap<String, (String, String)> = HashMap::new();
-        lines
-            .skip(1)
-            .map(|line| {
-                let node = line.split(" = ").collect_vec();
-                let re =
-                    Regex::new(r"\(([A-Z1-9]{3}), ([A-Z1-9]{3})\)").unwrap();
-                for (_, [left, right]) in
-                    re.captures_iter(node[1]).map(|c| c.extract())
-                {
-                    nodes.insert(
-                        node[0].to_string(),
-                        (left.to_string(), right.to_string()),
-                    );
-                }
-            })
-            .collect_vec();
-        Self { instrs, nodes }
+impl<'a> Network<'a> {
+    fn new(input: &'a str) -> parse::Result<Self> {
+        let input = Span::new(input);
+        let mut sections = input.sections();
+        let instrs = sections
+            .next()
+            .ok_or_else(|| input.error("expected instructions"))?
+            .trim()
+            .as_str()
+            .as_bytes();
+        let mut nodes = HashMap::new();
+        for section in sections {
+            for line in section.lines() {
+                let node = Node::from_span(line.trim())?;
+                nodes.insert(node.name, (node.left, node.right));
+            }
+        }
+        Ok(Self { instrs, nodes })
     }
 
-    fn next(&self, node: &str, instr: char) -> &str {
+    fn next(&self, node: &str, instr: u8) -> &'a str {
         match instr {
-            'L' => &self.nodes[node].0,
-            'R' => &self.nodes[node].1,
-            _ => panic!("unknown instruction {instr}"),
+            b'L' => self.nodes[node].0,
+            b'R' => self.nodes[node].1,
+            _ => panic!("unknown instruction {}", instr as char),
         }
     }
 
     fn aaa_to_zzz(&self) -> u64 {
+        // there are only so many (node, instruction) states, so a walk that
+        // takes more steps than that without reaching ZZZ is going in circles
+        let states = self.nodes.len() * self.instrs.len();
         let mut node = "AAA";
-        for (i, &instr) in self.instrs.iter().cycle().enumerate() {
-            node = self.next(node, instr);
+        let instrs = self.instrs.iter().cycle().take(states + 1);
+        for (steps, &instr) in instrs.enumerate() {
             if node == "ZZZ" {
-                return (i + 1) as u64;
+                return steps as u64;
             }
+            node = self.next(node, instr);
         }
-        unreachable!("no ZZZ node found");
+        panic!("no ZZZ node found")
+    }
+
+    /// Moves a ghost, which is at a node and some position in the
+    /// instructions.
+    fn step(&self, &(node, i): &(&'a str, usize)) -> (&'a str, usize) {
+        (self.next(node, self.instrs[i]), (i + 1) % self.instrs.len())
     }
 
     fn a_to_z(&self) -> u64 {
-        let mut current = self
+        let ghosts = self
             .nodes
             .keys()
             .filter(|node| node.ends_with('A'))
-            .map(String::from)
+            .map(|&start| {
+                let ghost = cycle::find_cycle((start, 0), |s| self.step(s));
+                explain::step(|| {
+                    format!(
+                        "ghost from {start}: loops every {} steps after {}",
+                        ghost.len, ghost.prefix
+                    )
+                });
+                ghost
+            })
             .collect_vec();
-        let mut z_steps = vec![0; current.len()];
-        for (i, &instr) in self.instrs.iter().cycle().enumerate() {
-            for node in current.iter_mut() {
-                *node = self.next(node, instr).to_string();
-            }
-            for (j, _) in current
+        if ghosts.is_empty() {
+            panic!("no nodes end in A, so there are no ghosts");
+        }
+        let at_z = |ghost: &Cycle<(&str, usize)>, n| {
+            ghost.state_at(n).0.ends_with('Z')
+        };
+
+        // until every ghost has settled into its cycle, just check each step
+        let settled = ghosts.iter().map(|g| g.prefix).max().unwrap_or(0);
+        if let Some(n) =
+            (0..settled).find(|&n| ghosts.iter().all(|g| at_z(g, n)))
+        {
+            return n as u64;
+        }
+
+        // after that, each ghost is at a Z on certain steps modulo its cycle
+        // length, so line those up one ghost at a time, keeping only the
+        // steps every ghost so far agrees on
+        let mut solutions = vec![(0, 1)];
+        for ghost in &ghosts {
+            let z_steps = (settled..settled + ghost.len)
+                .filter(|&n| at_z(ghost, n))
+                .map(|n| (n as i128, ghost.len as i128))
+                .collect_vec();
+            solutions = solutions
                 .iter()
-                .enumerate()
-                .filter(|(_, node)| node.ends_with('Z'))
-            {
-                if z_steps[j] == 0 {
-                    z_steps[j] = (i + 1) as u64;
-                }
-            }
-            if z_steps.iter().all(|&steps| steps > 0) {
-                break;
-            }
+                .cartesian_product(&z_steps)
+                .filter_map(|(&solution, &z)| math::crt(&[solution, z]))
+                .unique()
+                .collect();
         }
-        lcm(&z_steps)
+        let settled = settled as i128;
+        solutions
+            .into_iter()
+            .map(|(n, period)| {
+                // the first solution at or after `settled`
+                n + (settled - n + period - 1).max(0) / period * period
+            })
+            .min()
+            .expect("the ghosts never all reach Z at once") as u64
     }
 }
 
 pub fn part1(input: Option<String>) -> u64 {
-    let network = Network::new(input.unwrap_or_else(part1_example_input));
+    let input = input.unwrap_or_else(part1_example_input);
+    let network = Network::new(&input).unwrap();
     network.aaa_to_zzz()
 }
 
 pub fn part2(input: Option<String>) -> u64 {
-    let network = Network::new(input.unwrap_or_else(part2_example_input));
+    let input = input.unwrap_or_else(part2_example_input);
+    let network = Network::new(&input).unwrap();
     network.a_to_z()
 }
 
@@ -123,29 +154,115 @@ mod tests {
     use super::*;
 
     #[test]
-    fn test_gcd() {
-        // from https://en.wikipedia.org/wiki/Greatest_common_divisor
-        assert_eq!(gcd(48, 18), 6);
-        assert_eq!(gcd(54, 24), 6);
-        assert_eq!(gcd(48, 180), 12);
+    fn example_part1() {
+        assert_eq!(part1(None), 6);
+    }
+
+    #[test]
+    fn example_part2() {
+        assert_eq!(part2(None), 6);
     }
 
     #[test]
-    fn test_lcm() {
-        // from https://en.wikipedia.org/wiki/Least_common_multiple
-        assert_eq!(lcm(&[4, 6]), 12);
-        assert_eq!(lcm(&[21, 6]), 42);
-        assert_eq!(lcm(&[8, 9, 21]), 504);
-        assert_eq!(lcm(&[48, 180]), 720);
+    fn parse_errors() {
+        let err = Network::new("LR\n\nAAA = (BBB, CCC\n").err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "line 3, column 13: expected `)` after `{right}`"
+        );
+        let err = Network::new("LR\n\nAAA (BBB, CCC)\n").err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "line 3, column 1: expected ` = (` after `{name}`"
+        );
     }
 
     #[test]
-    fn example_part1() {
-        assert_eq!(part1(None), 6);
+    #[should_panic(expected = "no ZZZ node found")]
+    fn unreachable_zzz() {
+        let input =
+            "LR\n\nAAA = (BBB, BBB)\nBBB = (AAA, AAA)\nZZZ = (ZZZ, ZZZ)\n";
+        part1(Some(input.to_string()));
     }
 
     #[test]
-    fn example_part2() {
-        assert_eq!(part2(None), 6);
+    #[should_panic(expected = "no nodes end in A")]
+    fn no_ghosts() {
+        part2(Some(
+            "L\n\nBBB = (ZZZ, ZZZ)\nZZZ = (ZZZ, ZZZ)\n".to_string(),
+        ));
+    }
+
+    #[test]
+    fn z_not_at_cycle_start() {
+        // the first ghost is at a Z every 2 steps from step 2, the second
+        // every 3 from step 1, so they first line up at step 4 (not at the
+        // lcm of their first Zs)
+        let input = "\
+L
+
+11A = (11B, XXX)
+11B = (11Z, XXX)
+11Z = (11B, XXX)
+22A = (22Z, XXX)
+22Z = (22B, XXX)
+22B = (22C, XXX)
+22C = (22Z, XXX)
+XXX = (XXX, XXX)
+";
+        assert_eq!(part2(Some(input.to_string())), 4);
+    }
+
+    #[test]
+    fn z_before_settling() {
+        // both ghosts pass a Z at step 1, long before their cycles start
+        let input = "\
+L
+
+11A = (11Z, XXX)
+11Z = (11B, XXX)
+11B = (11C, XXX)
+11C = (11B, XXX)
+22A = (22Z, XXX)
+22Z = (22B, XXX)
+22B = (22B, XXX)
+XXX = (XXX, XXX)
+";
+        assert_eq!(part2(Some(input.to_string())), 1);
+    }
+
+    /// Compares copying node names into `String`s, as this day used to, with
+    /// borrowing them; run with `cargo test --release bench_network --
+    /// --ignored --nocapture --test-threads 1`.
+    #[test]
+    #[ignore]
+    fn bench_network() {
+        use crate::alloc;
+        use std::fmt::Write;
+
+        let mut input = "LR\n\n".to_string();
+        for i in 0..500_000 {
+            let (left, right) = ((i * 7 + 1) % 500_000, (i * 13 + 5) % 500_000);
+            writeln!(input, "{i:06} = ({left:06}, {right:06})").unwrap();
+        }
+        let (owned, owned_usage) = alloc::measure(|| {
+            let input = Span::new(&input);
+            let mut nodes = HashMap::new();
+            for line in input.sections().nth(1).unwrap().lines() {
+                let node = Node::from_span(line.trim()).unwrap();
+                nodes.insert(
+                    node.name.to_string(),
+                    (node.left.to_string(), node.right.to_string()),
+                );
+            }
+            nodes
+        });
+        let (borrowed, borrowed_usage) =
+            alloc::measure(|| Network::new(&input).unwrap());
+        assert_eq!(owned.len(), borrowed.nodes.len());
+        println!(
+            "{} nodes: String names {owned_usage}; borrowed {borrowed_usage}",
+            owned.len()
+        );
     }
 }
@@ -1,19 +1,19 @@
+use crate::explain;
+use crate::parse::Span;
 use itertools::Itertools;
+use std::io::BufRead;
 
 fn example_input() -> String {
     String::from_utf8_lossy(include_bytes!("day9_example.txt")).to_string()
 }
 
-fn parse_history(input: &str) -> Vec<i64> {
-    input
-        .split_whitespace()
-        .filter_map(|v| v.parse().ok())
-        .collect_vec()
+fn parse_history(line: Span) -> Vec<i64> {
+    line.ints().unwrap()
 }
 
-fn derive_sequences(history: &Vec<i64>) -> Vec<Vec<i64>> {
+fn derive_sequences(history: &[i64]) -> Vec<Vec<i64>> {
     let mut sequences = Vec::new();
-    let mut next_seq = history.clone();
+    let mut next_seq = history.to_vec();
     while next_seq.iter().any(|&v| v != 0) {
         sequences.push(next_seq.clone());
         next_seq = next_seq
@@ -42,27 +42,38 @@ fn extrapolate_backward(history: Vec<i64>) -> i64 {
 }
 
 fn extrapolate_sum(
-    input: Option<String>,
+    input: impl BufRead,
     extrapolate_fn: fn(Vec<i64>) -> i64,
-) -> i64 {
-    input
-        .unwrap_or_else(example_input)
+) -> u64 {
+    let sum: i64 = input
         .lines()
-        .map(parse_history)
-        .map(extrapolate_fn)
-        .sum()
+        .enumerate()
+        .map(|(i, line)| {
+            let line = line.unwrap();
+            let value =
+                extrapolate_fn(parse_history(Span::at_line(&line, i + 1)));
+            explain::step(|| format!("history {}: {value}", i + 1));
+            value
+        })
+        .sum();
+    assert!(sum >= 0, "negative sum {sum}");
+    sum as u64
 }
 
 pub fn part1(input: Option<String>) -> u64 {
-    let sum = extrapolate_sum(input, extrapolate);
-    assert!(sum >= 0, "negative sum {sum}");
-    sum as u64
+    part1_stream(input.unwrap_or_else(example_input).as_bytes())
+}
+
+pub fn part1_stream(input: impl BufRead) -> u64 {
+    extrapolate_sum(input, extrapolate)
 }
 
 pub fn part2(input: Option<String>) -> u64 {
-    let sum = extrapolate_sum(input, extrapolate_backward);
-    assert!(sum >= 0, "negative sum {sum}");
-    sum as u64
+    part2_stream(input.unwrap_or_else(example_input).as_bytes())
+}
+
+pub fn part2_stream(input: impl BufRead) -> u64 {
+    extrapolate_sum(input, extrapolate_backward)
 }
 
 #[cfg(test)]
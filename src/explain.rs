@@ -0,0 +1,62 @@
+//! Opt-in traces of how a solver reached its answer, shown by the dashboard.
+//!
+//! Solvers call [`step`] wherever a step is worth showing, such as a line of
+//! input and what it contributed. Outside [`record`] a step is only a
+//! thread-local check; the line itself is never formatted.
+
+use std::cell::RefCell;
+
+/// Steps kept per run; a real input can have thousands of lines.
+const MAX_STEPS: usize = 1000;
+
+thread_local! {
+    static TRACE: RefCell<Option<Trace>> = const { RefCell::new(None) };
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Trace {
+    pub steps: Vec<String>,
+    /// Steps left out once [`MAX_STEPS`] were kept.
+    pub dropped: usize,
+}
+
+/// Runs `f`, keeping the steps it records on this thread.
+pub fn record<T>(f: impl FnOnce() -> T) -> (T, Trace) {
+    TRACE.set(Some(Trace::default()));
+    let result = f();
+    (result, TRACE.take().unwrap_or_default())
+}
+
+/// Records a step, if a trace is being recorded.
+pub fn step(line: impl FnOnce() -> String) {
+    TRACE.with_borrow_mut(|trace| {
+        let Some(trace) = trace else {
+            return;
+        };
+        if trace.steps.len() < MAX_STEPS {
+            trace.steps.push(line());
+        } else {
+            trace.dropped += 1;
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records_only_when_asked() {
+        step(|| unreachable!("not recording"));
+        let (answer, trace) = record(|| {
+            for i in 0..MAX_STEPS + 2 {
+                step(|| format!("step {i}"));
+            }
+            7
+        });
+        assert_eq!(answer, 7);
+        assert_eq!(trace.steps[1], "step 1");
+        assert_eq!((trace.steps.len(), trace.dropped), (MAX_STEPS, 2));
+        step(|| unreachable!("no longer recording"));
+    }
+}
@@ -0,0 +1,357 @@
+//! Graph searches over implicit graphs: callers describe a graph by a
+//! neighbour function, so the same searches work on grids, networks of named
+//! nodes, or any other state space.
+//!
+//! Every search takes a `goal` predicate and stops as soon as a node
+//! satisfying it is reached (pass `|_| false` to explore everything), and
+//! returns the [`Paths`] it found.
+
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, VecDeque};
+use std::hash::Hash;
+#[cfg(test)]
+use std::{cmp::Reverse, collections::BinaryHeap, ops::Add};
+
+/// Edge weights for [`dijkstra`] and [`astar`], which no day searches with
+/// yet.
+#[cfg(test)]
+pub trait Cost: Copy + Ord + Add<Output = Self> + Default {}
+
+#[cfg(test)]
+impl<T: Copy + Ord + Add<Output = T> + Default> Cost for T {}
+
+/// What a search reached: the cost of getting to every visited node, and
+/// the tree of paths that achieve those costs.
+#[derive(Debug, Clone)]
+pub struct Paths<N, C> {
+    costs: HashMap<N, C>,
+    parents: HashMap<N, N>,
+    goal: Option<N>,
+}
+
+impl<N: Clone + Eq + Hash, C: Copy> Paths<N, C> {
+    fn new() -> Self {
+        Self {
+            costs: HashMap::new(),
+            parents: HashMap::new(),
+            goal: None,
+        }
+    }
+
+    /// Every reached node and its cost, in no particular order.
+    pub fn costs(&self) -> &HashMap<N, C> {
+        &self.costs
+    }
+
+    pub fn contains(&self, node: &N) -> bool {
+        self.costs.contains_key(node)
+    }
+}
+
+// day 10 only needs the costs, so until something asks for a path back the
+// rest is for the tests
+#[cfg(test)]
+impl<N: Clone + Eq + Hash, C: Copy> Paths<N, C> {
+    /// The node that ended the search early, if any.
+    pub fn goal(&self) -> Option<&N> {
+        self.goal.as_ref()
+    }
+
+    /// The cost of reaching the goal, if one was found.
+    pub fn goal_cost(&self) -> Option<C> {
+        self.cost(self.goal.as_ref()?)
+    }
+
+    pub fn cost(&self, node: &N) -> Option<C> {
+        self.costs.get(node).copied()
+    }
+
+    /// The number of reached nodes.
+    pub fn len(&self) -> usize {
+        self.costs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.costs.is_empty()
+    }
+
+    /// The nodes from a start node to `node`, inclusive.
+    pub fn path_to(&self, node: &N) -> Option<Vec<N>> {
+        if !self.contains(node) {
+            return None;
+        }
+        let mut path = vec![node.clone()];
+        while let Some(parent) = self.parents.get(path.last().unwrap()) {
+            path.push(parent.clone());
+        }
+        path.reverse();
+        Some(path)
+    }
+}
+
+/// Breadth-first search from every node in `starts` at once. Costs are the
+/// number of steps from the nearest start.
+pub fn bfs<N, I>(
+    starts: impl IntoIterator<Item = N>,
+    mut neighbors: impl FnMut(&N) -> I,
+    mut goal: impl FnMut(&N) -> bool,
+) -> Paths<N, usize>
+where
+    N: Clone + Eq + Hash,
+    I: IntoIterator<Item = N>,
+{
+    let mut paths = Paths::new();
+    let mut queue = VecDeque::new();
+    for start in starts {
+        if paths.contains(&start) {
+            continue;
+        }
+        paths.costs.insert(start.clone(), 0);
+        queue.push_back(start);
+    }
+    while let Some(node) = queue.pop_front() {
+        if goal(&node) {
+            paths.goal = Some(node);
+            break;
+        }
+        let cost = paths.costs[&node] + 1;
+        for next in neighbors(&node) {
+            if let Entry::Vacant(entry) = paths.costs.entry(next.clone()) {
+                entry.insert(cost);
+                paths.parents.insert(next.clone(), node.clone());
+                queue.push_back(next);
+            }
+        }
+    }
+    paths
+}
+
+/// Depth-first search from `start`. Costs are depths in the search tree,
+/// which aren't necessarily the shortest distances.
+#[cfg(test)]
+pub fn dfs<N, I>(
+    start: N,
+    mut neighbors: impl FnMut(&N) -> I,
+    mut goal: impl FnMut(&N) -> bool,
+) -> Paths<N, usize>
+where
+    N: Clone + Eq + Hash,
+    I: IntoIterator<Item = N>,
+{
+    let mut paths = Paths::new();
+    let mut stack = vec![(start, None)];
+    while let Some((node, parent)) = stack.pop() {
+        if paths.contains(&node) {
+            continue;
+        }
+        let cost = match parent {
+            Some(parent) => {
+                let cost = paths.costs[&parent] + 1;
+                paths.parents.insert(node.clone(), parent);
+                cost
+            }
+            None => 0,
+        };
+        paths.costs.insert(node.clone(), cost);
+        if goal(&node) {
+            paths.goal = Some(node);
+            break;
+        }
+        let mut next: Vec<_> = neighbors(&node)
+            .into_iter()
+            .filter(|next| !paths.contains(next))
+            .map(|next| (next, Some(node.clone())))
+            .collect();
+        // so that neighbours are visited in the order they were given
+        next.reverse();
+        stack.append(&mut next);
+    }
+    paths
+}
+
+/// Cheapest paths from every node in `starts` at once, where `neighbors`
+/// gives each neighbour along with the (non-negative) cost of moving there.
+#[cfg(test)]
+pub fn dijkstra<N, C, I>(
+    starts: impl IntoIterator<Item = N>,
+    neighbors: impl FnMut(&N) -> I,
+    goal: impl FnMut(&N) -> bool,
+) -> Paths<N, C>
+where
+    N: Clone + Eq + Hash,
+    C: Cost,
+    I: IntoIterator<Item = (N, C)>,
+{
+    astar(starts, neighbors, |_| C::default(), goal)
+}
+
+/// [`dijkstra`], guided towards the goal by `heuristic`, which must never
+/// overestimate the remaining cost for the goal's cost to be the cheapest.
+#[cfg(test)]
+pub fn astar<N, C, I>(
+    starts: impl IntoIterator<Item = N>,
+    mut neighbors: impl FnMut(&N) -> I,
+    mut heuristic: impl FnMut(&N) -> C,
+    mut goal: impl FnMut(&N) -> bool,
+) -> Paths<N, C>
+where
+    N: Clone + Eq + Hash,
+    C: Cost,
+    I: IntoIterator<Item = (N, C)>,
+{
+    let mut paths = Paths::new();
+    // nodes aren't necessarily Ord, so the heap holds indices into `queued`
+    let mut queued = Vec::new();
+    let mut heap = BinaryHeap::new();
+    for start in starts {
+        if paths.contains(&start) {
+            continue;
+        }
+        paths.costs.insert(start.clone(), C::default());
+        heap.push(Reverse((heuristic(&start), C::default(), queued.len())));
+        queued.push(start);
+    }
+    while let Some(Reverse((_, cost, i))) = heap.pop() {
+        let node = queued[i].clone();
+        if paths.costs[&node] < cost {
+            // already reached more cheaply
+            continue;
+        }
+        if goal(&node) {
+            paths.goal = Some(node);
+            break;
+        }
+        for (next, step) in neighbors(&node) {
+            let next_cost = cost + step;
+            if paths.cost(&next).is_some_and(|c| c <= next_cost) {
+                continue;
+            }
+            paths.costs.insert(next.clone(), next_cost);
+            paths.parents.insert(next.clone(), node.clone());
+            let priority = next_cost + heuristic(&next);
+            heap.push(Reverse((priority, next_cost, queued.len())));
+            queued.push(next);
+        }
+    }
+    paths
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid::Grid;
+    use crate::point::Point;
+
+    fn maze() -> Grid<char> {
+        Grid::parse(
+            "\
+S.#....
+.##.##.
+...#...
+.#...#E",
+            |c| c,
+        )
+    }
+
+    fn open_neighbors(grid: &Grid<char>, p: Point) -> Vec<Point> {
+        p.neighbors4()
+            .filter(|n| grid.get(n.x, n.y).is_some_and(|&c| c != '#'))
+            .collect()
+    }
+
+    #[test]
+    fn bfs_distances_and_paths() {
+        let grid = maze();
+        let end = Point::new(6, 3);
+        let paths =
+            bfs([Point::ORIGIN], |&p| open_neighbors(&grid, p), |_| false);
+        assert_eq!(paths.goal(), None);
+        assert_eq!(paths.cost(&end), Some(11));
+        assert_eq!(paths.cost(&Point::new(2, 0)), None);
+        let path = paths.path_to(&end).unwrap();
+        assert_eq!(path.len(), 12);
+        assert_eq!((path[0], path[11]), (Point::ORIGIN, end));
+        assert!(path.windows(2).all(|w| w[0].manhattan(w[1]) == 1));
+        assert_eq!(paths.path_to(&Point::ORIGIN), Some(vec![Point::ORIGIN]));
+    }
+
+    #[test]
+    fn bfs_stops_at_goal() {
+        let grid = maze();
+        let paths = bfs(
+            [Point::ORIGIN],
+            |&p| open_neighbors(&grid, p),
+            |&p| p.x == 3,
+        );
+        assert_eq!(paths.goal(), Some(&Point::new(3, 3)));
+        assert_eq!(paths.goal_cost(), Some(6));
+        assert!(!paths.contains(&Point::new(6, 3)));
+    }
+
+    #[test]
+    fn multiple_starts() {
+        let paths = bfs([0, 10], |&n: &i32| [n - 1, n + 1], |&n| n == 7);
+        assert_eq!(paths.goal_cost(), Some(3));
+        assert_eq!(paths.path_to(&7), Some(vec![10, 9, 8, 7]));
+    }
+
+    #[test]
+    fn dfs_reaches_everything() {
+        let grid = maze();
+        let all =
+            bfs([Point::ORIGIN], |&p| open_neighbors(&grid, p), |_| false);
+        let deep = dfs(Point::ORIGIN, |&p| open_neighbors(&grid, p), |_| false);
+        assert_eq!(deep.len(), all.len());
+        let none = bfs([], |&p| open_neighbors(&grid, p), |_| false);
+        assert!(none.is_empty() && !all.is_empty());
+        // a dfs path is a valid path, if not necessarily the shortest
+        for node in all.costs().keys() {
+            let path = deep.path_to(node).unwrap();
+            assert_eq!(path.len() - 1, deep.cost(node).unwrap());
+            assert!(path.len() > all.cost(node).unwrap());
+        }
+        // neighbours are tried in the order they're given
+        let line = dfs(0, |&n: &i32| [n + 1, n - 1], |&n| n == 3);
+        assert_eq!(line.path_to(&3), Some(vec![0, 1, 2, 3]));
+        assert!(!line.contains(&-1));
+    }
+
+    #[test]
+    fn weighted() {
+        // the direct edge is more expensive than the detour
+        let edges = HashMap::from([
+            ('a', vec![('b', 10), ('c', 1)]),
+            ('b', vec![('d', 1)]),
+            ('c', vec![('b', 2), ('d', 20)]),
+            ('d', vec![]),
+        ]);
+        let paths = dijkstra(['a'], |n| edges[n].clone(), |&n| n == 'd');
+        assert_eq!(paths.goal_cost(), Some(4));
+        assert_eq!(paths.path_to(&'d'), Some(vec!['a', 'c', 'b', 'd']));
+
+        let all: Paths<char, u32> =
+            dijkstra(['a'], |n| edges[n].clone(), |_| false);
+        assert_eq!(all.cost(&'b'), Some(3));
+    }
+
+    #[test]
+    fn astar_matches_dijkstra() {
+        let grid = maze();
+        let end = Point::new(6, 3);
+        let weighted = |&p: &Point| {
+            open_neighbors(&grid, p)
+                .into_iter()
+                .map(|n| (n, 1 + n.y as usize))
+                .collect::<Vec<_>>()
+        };
+        let plain = dijkstra([Point::ORIGIN], weighted, |&p| p == end);
+        let guided = astar(
+            [Point::ORIGIN],
+            weighted,
+            |p| p.manhattan(end),
+            |&p| p == end,
+        );
+        assert_eq!(guided.goal_cost(), plain.goal_cost());
+        assert!(guided.len() <= plain.len());
+    }
+}
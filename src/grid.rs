@@ -0,0 +1,599 @@
+//! A dense, rectangular 2D grid stored in row-major order.
+//!
+//! Positions are `(x, y)` pairs where `x` is the column and `y` the row, with
+//! `(0, 0)` in the top-left corner. Unchecked access goes through `Index`
+//! with `(usize, usize)`; checked access through `Grid::get` takes signed
+//! coordinates so that stepping off any edge is just a `None`.
+//!
+//! Grids can be transposed, rotated and flipped into new grids, and
+//! [`View`]s borrow a rectangular part of a grid, or of the puzzle text
+//! itself, without copying it. Both compare and hash by content, so repeated
+//! states can be detected with a `HashSet` or `HashMap`.
+
+use crate::point::Point;
+use std::hash::{Hash, Hasher};
+use std::ops::{Index, IndexMut};
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+
+impl<T> Grid<T> {
+    pub fn new(width: usize, height: usize, cells: Vec<T>) -> Self {
+        assert_eq!(cells.len(), width * height, "not a {width}x{height} grid");
+        Self {
+            width,
+            height,
+            cells,
+        }
+    }
+
+    pub fn from_fn(
+        width: usize,
+        height: usize,
+        mut cell: impl FnMut(usize, usize) -> T,
+    ) -> Self {
+        let cells = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .map(|(x, y)| cell(x, y))
+            .collect();
+        Self::new(width, height, cells)
+    }
+
+    pub fn contains(&self, x: isize, y: isize) -> bool {
+        (0..self.width as isize).contains(&x)
+            && (0..self.height as isize).contains(&y)
+    }
+
+    fn offsets(
+        &self,
+        x: usize,
+        y: usize,
+        offsets: &'static [Point],
+    ) -> impl Iterator<Item = (usize, usize)> + '_ {
+        offsets.iter().filter_map(move |d| {
+            let (nx, ny) = (x as isize + d.x, y as isize + d.y);
+            self.contains(nx, ny).then_some((nx as usize, ny as usize))
+        })
+    }
+
+    /// The orthogonal and diagonal neighbours of `(x, y)` that are inside the
+    /// grid.
+    pub fn neighbors8(
+        &self,
+        x: usize,
+        y: usize,
+    ) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.offsets(x, y, &Point::NEIGHBORS8)
+    }
+}
+
+// days 3 and 10 read their text through a `View`, so until a day reads an
+// owned grid back only the tests need these
+#[cfg(test)]
+impl<T> Grid<T> {
+    /// Parses one row per line, converting each character with `cell`.
+    pub fn parse(input: &str, mut cell: impl FnMut(char) -> T) -> Self {
+        let mut width = None;
+        let mut height = 0;
+        let mut cells = Vec::new();
+        for line in input.lines() {
+            let len = cells.len();
+            cells.extend(line.chars().map(&mut cell));
+            let row_width = cells.len() - len;
+            let width = *width.get_or_insert(row_width);
+            assert_eq!(row_width, width, "row {height} is not {width} wide");
+            height += 1;
+        }
+        Self::new(width.unwrap_or(0), height, cells)
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn get(&self, x: isize, y: isize) -> Option<&T> {
+        self.contains(x, y)
+            .then(|| &self.cells[y as usize * self.width + x as usize])
+    }
+
+    pub fn get_mut(&mut self, x: isize, y: isize) -> Option<&mut T> {
+        if self.contains(x, y) {
+            Some(&mut self.cells[y as usize * self.width + x as usize])
+        } else {
+            None
+        }
+    }
+
+    /// Every position, row by row.
+    pub fn positions(&self) -> impl Iterator<Item = (usize, usize)> {
+        let width = self.width;
+        (0..self.height).flat_map(move |y| (0..width).map(move |x| (x, y)))
+    }
+
+    /// Every position along with its cell, row by row.
+    pub fn iter(&self) -> impl Iterator<Item = ((usize, usize), &T)> {
+        self.positions().zip(self.cells.iter())
+    }
+
+    /// The orthogonal neighbours of `(x, y)` that are inside the grid.
+    pub fn neighbors4(
+        &self,
+        x: usize,
+        y: usize,
+    ) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.offsets(x, y, &Point::NEIGHBORS4)
+    }
+
+    pub fn row(&self, y: usize) -> &[T] {
+        &self.cells[y * self.width..(y + 1) * self.width]
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        // chunks() panics on a zero chunk size, i.e. an empty grid
+        self.cells.chunks(self.width.max(1))
+    }
+
+    pub fn column(&self, x: usize) -> impl Iterator<Item = &T> {
+        assert!(x < self.width, "column {x} out of bounds");
+        self.cells.iter().skip(x).step_by(self.width)
+    }
+
+    pub fn columns(&self) -> impl Iterator<Item = impl Iterator<Item = &T>> {
+        (0..self.width).map(|x| self.column(x))
+    }
+
+    /// Borrows the `width` by `height` rectangle whose top-left corner is at
+    /// `(x, y)`.
+    pub fn view(
+        &self,
+        x: usize,
+        y: usize,
+        width: usize,
+        height: usize,
+    ) -> View<'_, T> {
+        assert!(
+            x + width <= self.width && y + height <= self.height,
+            "{width}x{height} view at ({x}, {y}) out of bounds"
+        );
+        View::new(&self.cells, self.width, (x, y), width, height)
+    }
+}
+
+// nor does any day transform a grid yet
+#[cfg(test)]
+impl<T: Clone> Grid<T> {
+    fn remap(
+        &self,
+        width: usize,
+        height: usize,
+        source: impl Fn(usize, usize) -> (usize, usize),
+    ) -> Self {
+        Self::from_fn(width, height, |x, y| self[source(x, y)].clone())
+    }
+
+    /// Swaps rows and columns.
+    pub fn transpose(&self) -> Self {
+        self.remap(self.height, self.width, |x, y| (y, x))
+    }
+
+    pub fn rotate_clockwise(&self) -> Self {
+        let h = self.height;
+        self.remap(self.height, self.width, |x, y| (y, h - 1 - x))
+    }
+
+    pub fn rotate_counterclockwise(&self) -> Self {
+        let w = self.width;
+        self.remap(self.height, self.width, |x, y| (w - 1 - y, x))
+    }
+
+    pub fn rotate_180(&self) -> Self {
+        let (w, h) = (self.width, self.height);
+        self.remap(w, h, |x, y| (w - 1 - x, h - 1 - y))
+    }
+
+    /// Mirrors left to right.
+    pub fn flip_horizontal(&self) -> Self {
+        let w = self.width;
+        self.remap(self.width, self.height, |x, y| (w - 1 - x, y))
+    }
+
+    /// Mirrors top to bottom.
+    pub fn flip_vertical(&self) -> Self {
+        let h = self.height;
+        self.remap(self.width, self.height, |x, y| (x, h - 1 - y))
+    }
+}
+
+/// A rectangular window into a [`Grid`], or straight into the text of one
+/// (see [`View::parse_bytes`]), indexed relative to its own top-left corner.
+#[derive(Debug)]
+pub struct View<'a, T> {
+    /// The cells from the view's top-left corner on.
+    cells: &'a [T],
+    /// How far apart the starts of consecutive rows are in `cells`.
+    stride: usize,
+    width: usize,
+    height: usize,
+}
+
+// derive(Clone, Copy) would needlessly require T: Clone
+impl<T> Clone for View<'_, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for View<'_, T> {}
+
+impl<'a> View<'a, u8> {
+    /// Borrows one row per line of `input`, one byte per cell, without
+    /// copying it. Lines end in `\n`, or all in `\r\n` if the first does,
+    /// which the rows skip over (the last line may end the input instead).
+    pub fn parse_bytes(input: &'a str) -> Self {
+        let cells = input.as_bytes();
+        let lines = cells.strip_suffix(b"\n").unwrap_or(cells);
+        let first_len = lines
+            .iter()
+            .position(|&c| c == b'\n')
+            .unwrap_or(lines.len());
+        let crlf = lines[..first_len].ends_with(b"\r");
+        let width = first_len - crlf as usize;
+        let mut height = 0;
+        if !lines.is_empty() {
+            let mut rows = lines.split(|&c| c == b'\n').peekable();
+            while let Some(line) = rows.next() {
+                let row = match line.strip_suffix(b"\r") {
+                    Some(row) if crlf => row,
+                    // only the last line can be missing its `\r`
+                    None if crlf && rows.peek().is_some() => {
+                        panic!("row {height} ends in \\n, not \\r\\n")
+                    }
+                    _ => line,
+                };
+                assert_eq!(
+                    row.len(),
+                    width,
+                    "row {height} is not {width} wide"
+                );
+                height += 1;
+            }
+        }
+        Self {
+            cells,
+            stride: first_len + 1,
+            width,
+            height,
+        }
+    }
+}
+
+impl<'a, T> View<'a, T> {
+    /// The `width` by `height` rectangle at `(x, y)` in rows `stride` apart.
+    #[cfg(test)]
+    fn new(
+        cells: &'a [T],
+        stride: usize,
+        (x, y): (usize, usize),
+        width: usize,
+        height: usize,
+    ) -> Self {
+        // an empty view may start past the end of the cells
+        let start = (y * stride + x).min(cells.len());
+        Self {
+            cells: &cells[start..],
+            stride,
+            width,
+            height,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn contains(&self, x: isize, y: isize) -> bool {
+        (0..self.width as isize).contains(&x)
+            && (0..self.height as isize).contains(&y)
+    }
+
+    pub fn get(&self, x: isize, y: isize) -> Option<&'a T> {
+        self.contains(x, y)
+            .then(|| &self.row(y as usize)[x as usize])
+    }
+
+    /// Every position along with its cell, row by row.
+    pub fn iter(&self) -> impl Iterator<Item = ((usize, usize), &'a T)> + '_ {
+        self.rows().enumerate().flat_map(|(y, row)| {
+            row.iter().enumerate().map(move |(x, cell)| ((x, y), cell))
+        })
+    }
+
+    pub fn row(&self, y: usize) -> &'a [T] {
+        assert!(y < self.height, "row {y} out of bounds");
+        &self.cells[y * self.stride..][..self.width]
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &'a [T]> + '_ {
+        (0..self.height).map(|y| self.row(y))
+    }
+
+    /// A view of part of this view.
+    #[cfg(test)]
+    pub fn view(
+        &self,
+        x: usize,
+        y: usize,
+        width: usize,
+        height: usize,
+    ) -> Self {
+        assert!(
+            x + width <= self.width && y + height <= self.height,
+            "{width}x{height} view at ({x}, {y}) out of bounds"
+        );
+        Self::new(self.cells, self.stride, (x, y), width, height)
+    }
+
+    #[cfg(test)]
+    pub fn to_grid(self) -> Grid<T>
+    where
+        T: Clone,
+    {
+        Grid::from_fn(self.width, self.height, |x, y| self[(x, y)].clone())
+    }
+}
+
+impl<T> Index<(usize, usize)> for View<'_, T> {
+    type Output = T;
+
+    fn index(&self, (x, y): (usize, usize)) -> &T {
+        &self.row(y)[x]
+    }
+}
+
+impl<T: PartialEq> PartialEq for View<'_, T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.width == other.width && self.rows().eq(other.rows())
+    }
+}
+
+impl<T: Eq> Eq for View<'_, T> {}
+
+impl<T: Hash> Hash for View<'_, T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.width.hash(state);
+        self.height.hash(state);
+        for row in self.rows() {
+            row.iter().for_each(|cell| cell.hash(state));
+        }
+    }
+}
+
+impl<T> Index<(usize, usize)> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, (x, y): (usize, usize)) -> &T {
+        assert!(
+            x < self.width && y < self.height,
+            "({x}, {y}) out of bounds"
+        );
+        &self.cells[y * self.width + x]
+    }
+}
+
+impl<T> IndexMut<(usize, usize)> for Grid<T> {
+    fn index_mut(&mut self, (x, y): (usize, usize)) -> &mut T {
+        assert!(
+            x < self.width && y < self.height,
+            "({x}, {y}) out of bounds"
+        );
+        &mut self.cells[y * self.width + x]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn example() -> Grid<char> {
+        Grid::parse("abcd\nefgh\nijkl\n", |c| c)
+    }
+
+    #[test]
+    fn rectangular_indexing() {
+        let grid = example();
+        assert_eq!((grid.width(), grid.height()), (4, 3));
+        assert_eq!(grid[(0, 0)], 'a');
+        assert_eq!(grid[(3, 0)], 'd');
+        assert_eq!(grid[(1, 2)], 'j');
+        assert_eq!(grid[(3, 2)], 'l');
+        assert_eq!(grid.get(3, 1), Some(&'h'));
+        assert_eq!(grid.get(4, 1), None);
+        assert_eq!(grid.get(0, -1), None);
+        assert_eq!(grid.get(0, 3), None);
+        assert_eq!(grid.positions().nth(5), Some((1, 1)));
+        assert_eq!(
+            grid.iter().find(|&(_, &c)| c == 'k').map(|(pos, _)| pos),
+            Some((2, 2))
+        );
+    }
+
+    #[test]
+    fn neighbors() {
+        let grid = example();
+        assert_eq!(
+            grid.neighbors4(0, 0).collect::<Vec<_>>(),
+            vec![(1, 0), (0, 1)]
+        );
+        assert_eq!(grid.neighbors4(1, 1).count(), 4);
+        assert_eq!(grid.neighbors8(0, 0).count(), 3);
+        assert_eq!(grid.neighbors8(3, 1).count(), 5);
+        assert_eq!(grid.neighbors8(1, 1).count(), 8);
+    }
+
+    #[test]
+    fn rows_and_columns() {
+        let grid = example();
+        assert_eq!(grid.row(1), &['e', 'f', 'g', 'h']);
+        assert_eq!(grid.rows().count(), 3);
+        assert_eq!(grid.column(2).collect::<String>(), "cgk".to_string());
+        assert_eq!(
+            grid.columns()
+                .map(|c| c.collect::<String>())
+                .collect::<Vec<_>>(),
+            vec!["aei", "bfj", "cgk", "dhl"]
+        );
+    }
+
+    #[test]
+    fn mutation() {
+        let mut grid = example();
+        grid[(2, 1)] = '#';
+        *grid.get_mut(0, 2).unwrap() = '@';
+        assert_eq!(grid.row(1), &['e', 'f', '#', 'h']);
+        assert_eq!(grid.row(2)[0], '@');
+        assert!(grid.get_mut(5, 5).is_none());
+    }
+
+    #[test]
+    fn transforms() {
+        let grid = example();
+        let text = |grid: Grid<char>| {
+            grid.rows()
+                .map(|row| row.iter().collect::<String>())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(text(grid.transpose()), vec!["aei", "bfj", "cgk", "dhl"]);
+        assert_eq!(
+            text(grid.rotate_clockwise()),
+            vec!["iea", "jfb", "kgc", "lhd"]
+        );
+        assert_eq!(
+            text(grid.rotate_counterclockwise()),
+            vec!["dhl", "cgk", "bfj", "aei"]
+        );
+        assert_eq!(text(grid.rotate_180()), vec!["lkji", "hgfe", "dcba"]);
+        assert_eq!(text(grid.flip_horizontal()), vec!["dcba", "hgfe", "lkji"]);
+        assert_eq!(text(grid.flip_vertical()), vec!["ijkl", "efgh", "abcd"]);
+
+        assert_eq!(grid.rotate_clockwise().rotate_counterclockwise(), grid);
+        assert_eq!(
+            grid.rotate_clockwise().rotate_clockwise(),
+            grid.rotate_180()
+        );
+        assert_eq!(grid.transpose().transpose(), grid);
+        assert_eq!(grid.flip_horizontal().flip_vertical(), grid.rotate_180());
+    }
+
+    #[test]
+    fn views() {
+        let grid = example();
+        let view = grid.view(1, 1, 3, 2);
+        assert_eq!((view.width(), view.height()), (3, 2));
+        assert_eq!(view.row(0), &['f', 'g', 'h']);
+        assert_eq!(view[(0, 1)], 'j');
+        assert_eq!(view.get(2, 1), Some(&'l'));
+        assert_eq!(view.get(3, 0), None);
+        assert_eq!(view.view(1, 0, 2, 2).to_grid().rows().count(), 2);
+        assert_eq!(view.view(1, 0, 2, 2)[(1, 1)], 'l');
+        assert_eq!(view.to_grid(), Grid::parse("fgh\njkl\n", |c| c));
+    }
+
+    #[test]
+    fn borrowed_text() {
+        let input = "abcd\nefgh\nijkl\n";
+        let view = View::parse_bytes(input);
+        assert_eq!((view.width(), view.height()), (4, 3));
+        assert_eq!(view.row(2), b"ijkl");
+        assert_eq!(view.get(3, 1), Some(&b'h'));
+        assert_eq!(view.get(4, 1), None);
+        assert_eq!(view.view(1, 1, 3, 2).row(1), b"jkl");
+        assert_eq!(
+            view.iter().find(|&(_, &c)| c == b'g').map(|(pos, _)| pos),
+            Some((2, 1))
+        );
+        assert_eq!(View::parse_bytes("abcd\nefgh\nijkl"), view);
+        assert_eq!(view.to_grid(), Grid::parse(input, |c| c as u8),);
+        assert_eq!(View::parse_bytes("").height(), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "row 1 is not 3 wide")]
+    fn ragged_text() {
+        View::parse_bytes("abc\nde\n");
+    }
+
+    #[test]
+    fn crlf_text() {
+        let view = View::parse_bytes("abcd\r\nefgh\r\nijkl\r\n");
+        assert_eq!((view.width(), view.height()), (4, 3));
+        assert_eq!(view.row(1), b"efgh");
+        assert_eq!(view.get(3, 2), Some(&b'l'));
+        assert_eq!(view, View::parse_bytes("abcd\nefgh\nijkl\n"));
+        assert_eq!(View::parse_bytes("abcd\r\nefgh\r\nijkl").row(2), b"ijkl");
+        assert_eq!(View::parse_bytes("ab\r\n").row(0), b"ab");
+    }
+
+    #[test]
+    #[should_panic(expected = "row 1 ends in \\n, not \\r\\n")]
+    fn mixed_line_endings() {
+        View::parse_bytes("abc\r\ndef\nghi\r\n");
+    }
+
+    #[test]
+    fn repeated_states() {
+        use std::collections::HashSet;
+
+        let grid = Grid::parse("ab\nab\n", |c| c);
+        let mut seen = HashSet::new();
+        assert!(seen.insert(grid.clone()));
+        assert!(!seen.insert(grid.flip_vertical()));
+        assert!(seen.insert(grid.flip_horizontal()));
+
+        let mut views = HashSet::new();
+        assert!(views.insert(grid.view(0, 0, 2, 1)));
+        assert!(!views.insert(grid.view(0, 1, 2, 1)));
+        assert!(views.insert(grid.view(0, 0, 1, 2)));
+    }
+
+    #[test]
+    #[should_panic(expected = "row 1 is not 3 wide")]
+    fn ragged_input() {
+        Grid::parse("abc\nde\n", |c| c);
+    }
+
+    /// Compares copying a grid's text into a `Grid<char>`, as days 3 and 10
+    /// used to, with borrowing it; run with `cargo test --release
+    /// bench_parse_bytes -- --ignored --nocapture --test-threads 1`.
+    #[test]
+    #[ignore]
+    fn bench_parse_bytes() {
+        use crate::alloc;
+
+        let row = ".....+.58.".repeat(200) + "\n";
+        let input = row.repeat(2000);
+        let (owned, owned_usage) =
+            alloc::measure(|| Grid::parse(&input, |c| c));
+        let (borrowed, borrowed_usage) =
+            alloc::measure(|| View::parse_bytes(&input));
+        assert_eq!(
+            (owned.width(), owned.height()),
+            (borrowed.width(), borrowed.height())
+        );
+        println!(
+            "{} MB grid: Grid<char> {owned_usage}; borrowed {borrowed_usage}",
+            input.len() >> 20
+        );
+    }
+}
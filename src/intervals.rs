@@ -0,0 +1,371 @@
+//! Sets of half-open ranges, for puzzles that push whole ranges of values
+//! through a transformation instead of one value at a time.
+
+use std::fmt::Debug;
+use std::ops::{Add, Range, Sub};
+
+/// A set of values stored as sorted, disjoint, non-adjacent, non-empty
+/// half-open ranges.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct IntervalSet<T> {
+    ranges: Vec<Range<T>>,
+}
+
+impl<T> Default for IntervalSet<T> {
+    fn default() -> Self {
+        Self { ranges: Vec::new() }
+    }
+}
+
+impl<T: Copy + Ord> IntervalSet<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn ranges(&self) -> &[Range<T>] {
+        &self.ranges
+    }
+
+    #[cfg(test)]
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    pub fn min(&self) -> Option<T> {
+        self.ranges.first().map(|r| r.start)
+    }
+
+    #[cfg(test)]
+    pub fn contains(&self, value: T) -> bool {
+        // the last range starting at or before `value`
+        let i = self.ranges.partition_point(|r| r.start <= value);
+        i > 0 && value < self.ranges[i - 1].end
+    }
+
+    /// Adds every value in `range`, merging it with any ranges it overlaps
+    /// or touches.
+    pub fn insert(&mut self, range: Range<T>) {
+        if range.is_empty() {
+            return;
+        }
+        // ranges before `lo` end before `range`, and ranges from `hi` on
+        // start after it, so everything in between merges into one
+        let lo = self.ranges.partition_point(|r| r.end < range.start);
+        let hi = self.ranges.partition_point(|r| r.start <= range.end);
+        let merged = if lo < hi {
+            range.start.min(self.ranges[lo].start)
+                ..range.end.max(self.ranges[hi - 1].end)
+        } else {
+            range
+        };
+        self.ranges.splice(lo..hi, [merged]);
+    }
+
+    #[cfg(test)]
+    pub fn union(&self, other: &Self) -> Self {
+        let mut union = self.clone();
+        for range in &other.ranges {
+            union.insert(range.clone());
+        }
+        union
+    }
+
+    pub fn intersection(&self, other: &Self) -> Self {
+        let mut ranges = Vec::new();
+        let (mut i, mut j) = (0, 0);
+        while i < self.ranges.len() && j < other.ranges.len() {
+            let (a, b) = (&self.ranges[i], &other.ranges[j]);
+            let overlap = a.start.max(b.start)..a.end.min(b.end);
+            if !overlap.is_empty() {
+                ranges.push(overlap);
+            }
+            // whichever ends first can't overlap anything further on
+            if a.end < b.end {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+        Self { ranges }
+    }
+
+    /// The values in `self` that aren't in `other`.
+    pub fn difference(&self, other: &Self) -> Self {
+        let mut ranges = Vec::new();
+        let mut first = 0;
+        for range in &self.ranges {
+            // cuts ending before this range can't touch later ones either
+            while other
+                .ranges
+                .get(first)
+                .is_some_and(|c| c.end <= range.start)
+            {
+                first += 1;
+            }
+            let mut start = range.start;
+            for cut in other.ranges[first..]
+                .iter()
+                .take_while(|c| c.start < range.end)
+            {
+                if cut.start > start {
+                    ranges.push(start..cut.start);
+                }
+                start = start.max(cut.end);
+            }
+            if start < range.end {
+                ranges.push(start..range.end);
+            }
+        }
+        Self { ranges }
+    }
+
+    /// Splits `self` into its intersection with each of `parts`, in order,
+    /// followed by whatever isn't covered by any of them.
+    pub fn partition(&self, parts: &[Range<T>]) -> (Vec<Self>, Self) {
+        let mut rest = self.clone();
+        let pieces = parts
+            .iter()
+            .map(|part| {
+                let part = Self::from(part.clone());
+                rest = rest.difference(&part);
+                self.intersection(&part)
+            })
+            .collect();
+        (pieces, rest)
+    }
+}
+
+impl<T: Copy + Ord> From<Range<T>> for IntervalSet<T> {
+    fn from(range: Range<T>) -> Self {
+        let mut set = Self::new();
+        set.insert(range);
+        set
+    }
+}
+
+impl<T: Copy + Ord> FromIterator<Range<T>> for IntervalSet<T> {
+    fn from_iter<I: IntoIterator<Item = Range<T>>>(ranges: I) -> Self {
+        let mut set = Self::new();
+        for range in ranges {
+            set.insert(range);
+        }
+        set
+    }
+}
+
+/// A map that shifts each of its (disjoint) source ranges so that it starts
+/// at a destination value, and leaves everything else where it is.
+#[derive(Debug, Clone, Default)]
+pub struct OffsetMap<T> {
+    /// `(source, dest_start)`, sorted by source.
+    entries: Vec<(Range<T>, T)>,
+}
+
+impl<T> OffsetMap<T>
+where
+    T: Copy + Ord + Debug + Add<Output = T> + Sub<Output = T>,
+{
+    pub fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+        }
+    }
+
+    /// Maps `source` onto the range of the same length starting at `dest`.
+    ///
+    /// # Panics
+    ///
+    /// If `source` overlaps a source range already in the map.
+    pub fn insert(&mut self, source: Range<T>, dest: T) {
+        if source.is_empty() {
+            return;
+        }
+        let i = self
+            .entries
+            .partition_point(|(s, _)| s.start < source.start);
+        let overlaps = |j: usize| {
+            self.entries.get(j).is_some_and(|(s, _)| {
+                s.start < source.end && source.start < s.end
+            })
+        };
+        assert!(
+            !overlaps(i) && (i == 0 || !overlaps(i - 1)),
+            "{source:?} overlaps an existing source range"
+        );
+        self.entries.insert(i, (source, dest));
+    }
+
+    fn shift(source: &Range<T>, dest: T, value: T) -> T {
+        // ordered so that unsigned values never go negative
+        value - source.start + dest
+    }
+
+    pub fn get(&self, value: T) -> T {
+        let i = self.entries.partition_point(|(s, _)| s.start <= value);
+        match i.checked_sub(1).map(|i| &self.entries[i]) {
+            Some((source, dest)) if value < source.end => {
+                Self::shift(source, *dest, value)
+            }
+            _ => value,
+        }
+    }
+
+    /// Every value that some value in `set` maps to.
+    pub fn image(&self, set: &IntervalSet<T>) -> IntervalSet<T> {
+        let sources: Vec<_> =
+            self.entries.iter().map(|(s, _)| s.clone()).collect();
+        let (pieces, mut image) = set.partition(&sources);
+        for (piece, (source, dest)) in pieces.iter().zip(&self.entries) {
+            for range in piece.ranges() {
+                image.insert(
+                    Self::shift(source, *dest, range.start)
+                        ..Self::shift(source, *dest, range.end),
+                );
+            }
+        }
+        image
+    }
+}
+
+#[cfg(test)]
+// single-range sets are exactly what some of these tests want
+#[allow(clippy::single_range_in_vec_init)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+    use std::collections::BTreeSet;
+
+    fn set(ranges: &[Range<i32>]) -> IntervalSet<i32> {
+        ranges.iter().cloned().collect()
+    }
+
+    fn brute(set: &IntervalSet<i32>) -> BTreeSet<i32> {
+        set.ranges().iter().flat_map(|r| r.clone()).collect()
+    }
+
+    fn is_normalized(set: &IntervalSet<i32>) -> bool {
+        set.ranges().iter().all(|r| !r.is_empty())
+            && set.ranges().windows(2).all(|w| w[0].end < w[1].start)
+    }
+
+    #[test]
+    fn insert_merges() {
+        let mut s = set(&[0..2, 5..7]);
+        s.insert(2..3);
+        assert_eq!(s.ranges(), [0..3, 5..7]);
+        s.insert(4..5);
+        assert_eq!(s.ranges(), [0..3, 4..7]);
+        s.insert(-5..10);
+        assert_eq!(s.ranges(), [-5..10]);
+        s.insert(20..20);
+        assert_eq!(s.ranges(), [-5..10]);
+    }
+
+    #[test]
+    fn set_operations() {
+        let a = set(&[0..10, 20..30]);
+        let b = set(&[5..25]);
+        assert_eq!(a.union(&b).ranges(), [0..30]);
+        assert_eq!(a.intersection(&b).ranges(), [5..10, 20..25]);
+        assert_eq!(a.difference(&b).ranges(), [0..5, 25..30]);
+        assert_eq!(b.difference(&a).ranges(), [10..20]);
+        assert!(a.contains(0) && a.contains(29) && !a.contains(10));
+        assert!(a.intersection(&set(&[10..20])).is_empty());
+        assert!(!b.is_empty() && IntervalSet::<i32>::new().is_empty());
+    }
+
+    #[test]
+    fn partition() {
+        let (pieces, rest) = set(&[0..10, 20..30]).partition(&[8..22, 25..26]);
+        assert_eq!(pieces[0].ranges(), [8..10, 20..22]);
+        assert_eq!(pieces[1].ranges(), [25..26]);
+        assert_eq!(rest.ranges(), [0..8, 22..25, 26..30]);
+    }
+
+    #[test]
+    fn offset_map() {
+        // day 5's seed-to-soil map
+        let mut map = OffsetMap::new();
+        map.insert(98_u64..100, 50);
+        map.insert(50..98, 52);
+        assert_eq!(map.get(0), 0);
+        assert_eq!(map.get(50), 52);
+        assert_eq!(map.get(99), 51);
+        assert_eq!(map.get(100), 100);
+        let seeds = IntervalSet::from_iter([79..93, 55..68]);
+        assert_eq!(map.image(&seeds).ranges(), [57..70, 81..95]);
+        let edges = IntervalSet::from(96..102);
+        assert_eq!(map.image(&edges).ranges(), [50..52, 98..102]);
+    }
+
+    #[test]
+    #[should_panic(expected = "overlaps")]
+    fn overlapping_offsets() {
+        let mut map = OffsetMap::new();
+        map.insert(0..10, 100);
+        map.insert(5..15, 200);
+    }
+
+    fn ranges() -> impl Strategy<Value = Vec<Range<i32>>> {
+        prop::collection::vec(
+            (-50..50, 0..20).prop_map(|(s, l)| s..s + l),
+            0..8,
+        )
+    }
+
+    proptest! {
+        #[test]
+        fn matches_brute_force(a in ranges(), b in ranges(), x in -60..80) {
+            let (a, b) = (set(&a), set(&b));
+            let (ba, bb) = (brute(&a), brute(&b));
+            prop_assert!(is_normalized(&a));
+            prop_assert_eq!(a.contains(x), ba.contains(&x));
+
+            let union = a.union(&b);
+            prop_assert!(is_normalized(&union));
+            prop_assert_eq!(brute(&union), &ba | &bb);
+
+            let intersection = a.intersection(&b);
+            prop_assert!(is_normalized(&intersection));
+            prop_assert_eq!(brute(&intersection), &ba & &bb);
+
+            let difference = a.difference(&b);
+            prop_assert!(is_normalized(&difference));
+            prop_assert_eq!(brute(&difference), &ba - &bb);
+        }
+
+        #[test]
+        fn partition_covers(a in ranges(), parts in ranges()) {
+            let a = set(&a);
+            let (pieces, rest) = a.partition(&parts);
+            let mut covered = brute(&rest);
+            for (piece, part) in pieces.iter().zip(&parts) {
+                prop_assert!(brute(piece).iter().all(|v| part.contains(v)));
+                covered.extend(brute(piece));
+            }
+            prop_assert_eq!(covered, brute(&a));
+            prop_assert!(brute(&rest)
+                .iter()
+                .all(|v| parts.iter().all(|p| !p.contains(v))));
+        }
+
+        #[test]
+        fn image_matches_pointwise(
+            a in ranges(),
+            entries in prop::collection::vec((0..10, 1..6, -40..40), 0..6),
+        ) {
+            // lay the sources out end to end so they can't overlap
+            let mut map = OffsetMap::new();
+            let mut start = -50;
+            for (gap, len, dest) in entries {
+                start += gap;
+                map.insert(start..start + len, dest);
+                start += len;
+            }
+            let a = set(&a);
+            let expected: BTreeSet<_> =
+                brute(&a).into_iter().map(|v| map.get(v)).collect();
+            prop_assert_eq!(brute(&map.image(&a)), expected);
+        }
+    }
+}
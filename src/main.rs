@@ -1,5 +1,11 @@
+mod aho_corasick;
+#[cfg(test)]
+mod alloc;
+mod catch;
+mod cycle;
 mod day0;
 mod day1;
+mod day10;
 mod day2;
 mod day3;
 mod day4;
@@ -8,29 +14,261 @@ mod day6;
 mod day7;
 mod day8;
 mod day9;
-mod day10;
+mod explain;
+mod graph;
+mod grid;
+mod intervals;
+mod math;
+mod minimize;
+mod parse;
+mod point;
+mod serve;
+#[cfg(test)]
+mod sparse_grid;
+mod tui;
 
 use std::env;
 use std::fs::File;
-use std::io::{self, Read};
+use std::io::{self, BufRead, BufReader, Read};
+use std::path::Path;
+
+type Solver = fn(Option<String>) -> u64;
+type StreamSolver = fn(Box<dyn BufRead>) -> u64;
 
 fn read_from_filename_or_stdin(filename: String) -> String {
     let mut input = String::new();
-    if filename == "-" {
-        io::stdin().read_to_string(&mut input).unwrap();
+    let read = if filename == "-" {
+        io::stdin().read_to_string(&mut input)
     } else {
-        let mut file = File::open(filename).unwrap();
-        file.read_to_string(&mut input).unwrap();
+        File::open(&filename)
+            .and_then(|mut file| file.read_to_string(&mut input))
+    };
+    if let Err(err) = read {
+        exit_with(&format!("{filename}: {err}"));
     }
     input
 }
 
+fn open_filename_or_stdin(filename: String) -> Box<dyn BufRead> {
+    if filename == "-" {
+        return Box::new(io::stdin().lock());
+    }
+    match File::open(&filename) {
+        Ok(file) => Box::new(BufReader::new(file)),
+        Err(err) => exit_with(&format!("{filename}: {err}")),
+    }
+}
+
+/// Every implemented puzzle, as `N.P` names.
+const PUZZLES: &[(&str, Solver)] = &[
+    ("0.1", day0::part1),
+    ("0.2", day0::part2),
+    ("1.1", day1::part1),
+    ("1.2", day1::part2),
+    ("2.1", day2::part1),
+    ("2.2", day2::part2),
+    ("3.1", day3::part1),
+    ("3.2", day3::part2),
+    ("4.1", day4::part1),
+    ("4.2", day4::part2),
+    ("5.1", day5::part1),
+    ("5.2", day5::part2),
+    ("6.1", day6::part1),
+    ("6.2", day6::part2),
+    ("7.1", day7::part1),
+    ("7.2", day7::part2),
+    ("8.1", day8::part1),
+    ("8.2", day8::part2),
+    ("9.1", day9::part1),
+    ("9.2", day9::part2),
+    ("10.1", day10::part1),
+];
+
+fn solver(puzzle: &str) -> Option<Solver> {
+    let puzzle = puzzle.strip_prefix("day").unwrap_or(puzzle);
+    PUZZLES
+        .iter()
+        .find(|&&(name, _)| name == puzzle)
+        .map(|&(_, solver)| solver)
+}
+
+/// Puzzles that can process their input a line at a time, without reading
+/// all of it into memory first.
+fn stream_solver(puzzle: &str) -> Option<StreamSolver> {
+    let solver: StreamSolver =
+        match puzzle.strip_prefix("day").unwrap_or(puzzle) {
+            "2.1" => day2::part1_stream,
+            "2.2" => day2::part2_stream,
+            "4.1" => day4::part1_stream,
+            "4.2" => day4::part2_stream,
+            "7.1" => day7::part1_stream,
+            "7.2" => day7::part2_stream,
+            "9.1" => day9::part1_stream,
+            "9.2" => day9::part2_stream,
+            _ => return None,
+        };
+    Some(solver)
+}
+
+/// Puzzles with a second implementation that shares none of the first's
+/// logic, so that `minimize --disagree` can check one against the other.
+fn second_solver(puzzle: &str) -> Option<Solver> {
+    let solver: Solver = match puzzle.strip_prefix("day").unwrap_or(puzzle) {
+        "1.1" => day1::part1_lines,
+        "5.1" => day5::part1_ranges,
+        _ => return None,
+    };
+    Some(solver)
+}
+
+/// Removes `flag` and the value after it from `args`, returning the value.
+fn take_option(args: &mut Vec<String>, flag: &str) -> Option<String> {
+    let i = args.iter().position(|arg| arg == flag)?;
+    if i + 1 == args.len() {
+        exit_with(&format!("{flag} needs a value"));
+    }
+    args.remove(i);
+    Some(args.remove(i))
+}
+
+/// Day 1's options, if any of them were given. `args` should hold only the
+/// puzzle and its input by now, so that the input can be checked against
+/// the options.
+fn day1_options(args: &mut Vec<String>) -> Option<day1::Options> {
+    let lang = take_option(args, "--lang");
+    let vocab = take_option(args, "--vocab");
+    let compound = take_option(args, "--compound");
+    let report = take_flag(args, "--report");
+    let skip_missing = take_flag(args, "--skip-missing");
+    let input = args.get(2);
+    // the example input only spells its digits out in English
+    if input.is_none()
+        && (lang.as_deref().is_some_and(|lang| lang != "en") || vocab.is_some())
+    {
+        exit_with("--lang and --vocab need an input to run on");
+    }
+    let calibrator = match (lang, vocab, compound) {
+        (None, None, None) => None,
+        (Some(lang), None, None) => {
+            let vocab = day1::Vocabulary::builtin(&lang).unwrap_or_else(|| {
+                exit_with(&format!(
+                    "unknown language: {lang} (expected one of {})",
+                    day1::Vocabulary::LANGUAGES.join(", ")
+                ))
+            });
+            Some(day1::Calibrator::words(&vocab))
+        }
+        (None, Some(path), None) => {
+            if path == "-" && input.is_some_and(|input| input == "-") {
+                exit_with(
+                    "--vocab and the input can't both be read from stdin",
+                );
+            }
+            let vocab = read_from_filename_or_stdin(path.clone());
+            let vocab = day1::Vocabulary::parse(&vocab)
+                .unwrap_or_else(|err| exit_with(&format!("{path}: {err}")));
+            Some(day1::Calibrator::words(&vocab))
+        }
+        (None, None, Some(rule)) => {
+            let rule =
+                day1::MultiDigitRule::from_name(&rule).unwrap_or_else(|| {
+                    exit_with(&format!(
+                        "unknown rule: {rule} (expected one of {})",
+                        day1::MultiDigitRule::NAMES.join(", ")
+                    ))
+                });
+            Some(day1::Calibrator::compounds(rule))
+        }
+        _ => {
+            exit_with("only one of --lang, --vocab and --compound can be given")
+        }
+    };
+    (calibrator.is_some() || report || skip_missing).then_some(day1::Options {
+        calibrator,
+        report,
+        skip_missing,
+    })
+}
+
+/// Day 2's options, if any of them were given.
+fn day2_options(args: &mut Vec<String>) -> Option<day2::Options> {
+    let bag =
+        match (take_option(args, "--bag"), take_option(args, "--bag-file")) {
+            (None, None) => None,
+            (Some(bag), None) => Some(
+                day2::Bag::parse(&bag)
+                    .unwrap_or_else(|err| exit_with(&format!("--bag: {err}"))),
+            ),
+            (None, Some(path)) => {
+                let bag = read_from_filename_or_stdin(path.clone());
+                Some(
+                    day2::Bag::parse(&bag).unwrap_or_else(|err| {
+                        exit_with(&format!("{path}: {err}"))
+                    }),
+                )
+            }
+            _ => exit_with("only one of --bag and --bag-file can be given"),
+        };
+    let reject_unknown = take_flag(args, "--reject-unknown");
+    (bag.is_some() || reject_unknown).then(|| day2::Options {
+        bag: bag.unwrap_or_default(),
+        unknown_colors: if reject_unknown {
+            day2::UnknownColors::Reject
+        } else {
+            day2::UnknownColors::Impossible
+        },
+    })
+}
+
+/// Prints `message` and exits, for command lines that can't be run.
+fn exit_with(message: &str) -> ! {
+    eprintln!("{message}");
+    std::process::exit(1);
+}
+
+/// Removes `flag` from `args`, returning whether it was there.
+fn take_flag(args: &mut Vec<String>, flag: &str) -> bool {
+    let len = args.len();
+    args.retain(|arg| arg != flag);
+    args.len() != len
+}
+
 fn usage() {
+    let program = env::args().next().unwrap();
     println!("Advent of Code 2023");
+    println!("usage: {program} [--help|-h] [day]N.P [input]");
+    println!(
+        concat!(
+            "       {program} [day]1.P [--report] [--skip-missing] ",
+            "[--lang LANG|--vocab FILE|--compound RULE] [input]"
+        ),
+        program = program
+    );
+    println!(
+        concat!(
+            "       {program} [day]2.P [--bag CUBES|--bag-file FILE] ",
+            "[--reject-unknown] [input]"
+        ),
+        program = program
+    );
+    println!("       {program} minimize [day]N.P [--disagree] input [corpus]");
+    println!("       {program} json [day]2 [input]");
+    println!(
+        concat!(
+            "       {program} query [day]2 QUERY [--json] [--bag CUBES|",
+            "--bag-file FILE] [input]"
+        ),
+        program = program
+    );
     println!(
-        "usage: {} [--help|-h] [day]N.P [input]",
-        env::args().nth(0).unwrap()
+        concat!(
+            "       {program} infer [day]2 [--json] [--size N] [--bags FILE|",
+            "--bag CUBES] [input]"
+        ),
+        program = program
     );
+    println!("       {program} serve [--port N]");
+    println!("       {program} tui");
     println!();
     println!("N: Which day's puzzle to solve (1..25)");
     println!("P: Which part of the puzzle to solve (either 1 or 2)");
@@ -38,6 +276,62 @@ fn usage() {
         "input: Path to an input file; if omitted, use ",
         "example input for that day's puzzle"
     ));
+    println!(concat!(
+        "--lang: Spell out day 1's digits in another language (en, fr, de, ",
+        "es; default: en), on an input rather than the English example"
+    ));
+    println!(concat!(
+        "--vocab: Read day 1's digit words from `word: digit` lines ",
+        "in FILE, which can't be stdin if the input is"
+    ));
+    println!(concat!(
+        "--compound: Read day 1's numbers as compound English words like ",
+        "\"twenty-three\", combining the first and last by RULE (edges, ",
+        "leading, trailing, concat)"
+    ));
+    println!("--report: Show how each of day 1's lines was calibrated");
+    println!(concat!(
+        "--skip-missing: Leave out day 1's lines without digits instead of ",
+        "failing"
+    ));
+    println!(concat!(
+        "--bag: Play day 2 with CUBES in the bag, like \"2 red, 5 purple\" ",
+        "(default: 12 red, 13 green, 14 blue)"
+    ));
+    println!("--bag-file: Read day 2's bag from FILE, one or more per line");
+    println!(concat!(
+        "--reject-unknown: Fail on day 2's colours that aren't in the bag ",
+        "instead of counting their games as impossible"
+    ));
+    println!(concat!(
+        "minimize: Shrink an input that makes the puzzle panic and save ",
+        "it to corpus (default: corpus/)"
+    ));
+    println!(concat!(
+        "--disagree: Shrink an input on which the puzzle's two independent ",
+        "implementations give different answers instead (1.1 and 5.1 only)"
+    ));
+    println!("json: Print day 2's games as JSON");
+    println!(concat!(
+        "query: Ask day 2's games a question, like \"sum(id) where power > ",
+        "500\" or \"count by colour where max colour > bag colour\", and ",
+        "print the answer as a table (or JSON, with --json)"
+    ));
+    println!(concat!(
+        "infer: Estimate each of day 2's bags of N cubes (default: as many ",
+        "as the first bag, or the most any game showed), if cubes were drawn ",
+        "with replacement, and check which bags (one per line in FILE) fit ",
+        "every game, with one test per game corrected for testing them all"
+    ));
+    println!(concat!(
+        "serve: Answer `POST /solve/N/P` and `GET /puzzles` over HTTP on ",
+        "localhost (default port: 8023)"
+    ));
+    println!(concat!(
+        "tui: Show a dashboard of every day, running puzzles against example ",
+        "input (checking its known answers) or inputs/dayN.txt, with an ",
+        "explain trace of each part"
+    ));
 }
 
 fn main() {
@@ -47,33 +341,208 @@ fn main() {
             std::process::exit(0);
         }
     }
-    let puzzle = env::args()
-        .nth(1)
-        .expect("needs a puzzle to run, e.g. `day1.1` or `1.1`");
-    let input = env::args().nth(2).map(read_from_filename_or_stdin);
-    let output = match &puzzle[..] {
-        "day0.1" | "0.1" => day0::part1(input),
-        "day0.2" | "0.2" => day0::part2(input),
-        "day1.1" | "1.1" => day1::part1(input),
-        "day1.2" | "1.2" => day1::part2(input),
-        "day2.1" | "2.1" => day2::part1(input),
-        "day2.2" | "2.2" => day2::part2(input),
-        "day3.1" | "3.1" => day3::part1(input),
-        "day3.2" | "3.2" => day3::part2(input),
-        "day4.1" | "4.1" => day4::part1(input),
-        "day4.2" | "4.2" => day4::part2(input),
-        "day5.1" | "5.1" => day5::part1(input),
-        "day5.2" | "5.2" => day5::part2(input),
-        "day6.1" | "6.1" => day6::part1(input),
-        "day6.2" | "6.2" => day6::part2(input),
-        "day7.1" | "7.1" => day7::part1(input),
-        "day7.2" | "7.2" => day7::part2(input),
-        "day8.1" | "8.1" => day8::part1(input),
-        "day8.2" | "8.2" => day8::part2(input),
-        "day9.1" | "9.1" => day9::part1(input),
-        "day9.2" | "9.2" => day9::part2(input),
-        "day10.1" | "10.1" => day10::part1(input),
-        _ => panic!("unknown puzzle: {}", puzzle),
+    let mut args: Vec<String> = env::args().collect();
+    let day2_options = day2_options(&mut args);
+    let day1_options = day1_options(&mut args);
+    let puzzle = args.get(1).cloned().unwrap_or_else(|| {
+        exit_with("needs a puzzle to run, e.g. `day1.1` or `1.1`")
+    });
+    if puzzle == "minimize" {
+        let disagree = take_flag(&mut args, "--disagree");
+        let puzzle = args
+            .get(2)
+            .cloned()
+            .unwrap_or_else(|| exit_with("needs a puzzle to minimize"));
+        let solver = solver(&puzzle)
+            .unwrap_or_else(|| exit_with(&format!("unknown puzzle: {puzzle}")));
+        let input = args
+            .get(3)
+            .cloned()
+            .map(read_from_filename_or_stdin)
+            .unwrap_or_else(|| exit_with("needs an input to minimize"));
+        let corpus = args.get(4).cloned().unwrap_or_else(|| "corpus".into());
+        let corpus = Path::new(&corpus);
+        let minimized = if disagree {
+            let second_solver = second_solver(&puzzle).unwrap_or_else(|| {
+                exit_with(&format!("{puzzle} has only one implementation"))
+            });
+            minimize::minimize_disagreement(
+                &puzzle,
+                solver,
+                second_solver,
+                &input,
+                corpus,
+            )
+        } else {
+            minimize::minimize(&puzzle, solver, &input, corpus)
+        };
+        match minimized {
+            Some(path) => println!("{}", path.display()),
+            None if disagree => {
+                eprintln!(
+                    "{puzzle}'s implementations agree on the given input"
+                );
+                std::process::exit(1);
+            }
+            None => {
+                eprintln!("{puzzle} does not fail on the given input");
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+    if puzzle == "json" {
+        let day = args
+            .get(2)
+            .cloned()
+            .unwrap_or_else(|| exit_with("needs a day to export"));
+        if day.strip_prefix("day").unwrap_or(&day) != "2" {
+            exit_with("only day 2 can be exported as JSON");
+        }
+        let input: Box<dyn BufRead> = match args.get(3).cloned() {
+            Some(filename) => open_filename_or_stdin(filename),
+            None => Box::new(io::Cursor::new(day2::example_input())),
+        };
+        match day2::to_json(input) {
+            Ok(json) => println!("{json}"),
+            Err(err) => {
+                eprintln!("{err}");
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+    if puzzle == "query" {
+        let json = take_flag(&mut args, "--json");
+        let day = args
+            .get(2)
+            .cloned()
+            .unwrap_or_else(|| exit_with("needs a day to query"));
+        if day.strip_prefix("day").unwrap_or(&day) != "2" {
+            exit_with("only day 2 can be queried");
+        }
+        let query = args
+            .get(3)
+            .cloned()
+            .unwrap_or_else(|| exit_with("needs a query to run"));
+        let input: Box<dyn BufRead> = match args.get(4).cloned() {
+            Some(filename) => open_filename_or_stdin(filename),
+            None => Box::new(io::Cursor::new(day2::example_input())),
+        };
+        let bag = day2_options.unwrap_or_default().bag;
+        match day2::query::run(&query, input, &bag) {
+            Ok(table) if json => println!("{}", table.to_json()),
+            Ok(table) => print!("{table}"),
+            Err(err) => {
+                eprintln!("{err}");
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+    if puzzle == "infer" {
+        let json = take_flag(&mut args, "--json");
+        let bags = take_option(&mut args, "--bags");
+        let size = take_option(&mut args, "--size").map(|size| {
+            size.parse().unwrap_or_else(|_| exit_with("invalid size"))
+        });
+        let day = args
+            .get(2)
+            .cloned()
+            .unwrap_or_else(|| exit_with("needs a day to infer"));
+        if day.strip_prefix("day").unwrap_or(&day) != "2" {
+            exit_with("only day 2's bag can be inferred");
+        }
+        let bags = match bags {
+            Some(_) if day2_options.is_some() => exit_with(
+                "--bags can't be combined with --bag, --bag-file or \
+                 --reject-unknown",
+            ),
+            Some(path) => {
+                let bags = read_from_filename_or_stdin(path.clone());
+                day2::Bag::parse_list(&bags)
+                    .unwrap_or_else(|err| exit_with(&format!("{path}: {err}")))
+            }
+            None => vec![day2_options.unwrap_or_default().bag],
+        };
+        let input: Box<dyn BufRead> = match args.get(3).cloned() {
+            Some(filename) => open_filename_or_stdin(filename),
+            None => Box::new(io::Cursor::new(day2::example_input())),
+        };
+        match day2::infer::run(input, &bags, size) {
+            Ok(inference) if json => println!("{}", inference.to_json()),
+            Ok(inference) => print!("{inference}"),
+            Err(err) => {
+                eprintln!("{err}");
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+    if puzzle == "serve" {
+        let port = match (args.get(2).cloned(), args.get(3).cloned()) {
+            (Some(flag), Some(port)) if flag == "--port" => {
+                port.parse().unwrap_or_else(|_| exit_with("invalid port"))
+            }
+            (None, _) => 8023,
+            _ => exit_with("usage: serve [--port N]"),
+        };
+        serve::serve(port);
+        return;
+    }
+    if puzzle == "tui" {
+        tui::tui().unwrap();
+        return;
+    }
+    // day 1 always goes through its own runner, so that a line without
+    // digits is reported rather than panicking
+    let day1_part = match puzzle.strip_prefix("day").unwrap_or(&puzzle) {
+        "1.1" => Some(1),
+        "1.2" => Some(2),
+        _ => None,
     };
-    println!("{}", output);
+    if day1_options.is_some() && day1_part.is_none() {
+        exit_with(&format!("{puzzle} doesn't take day 1's options"));
+    }
+    if let Some(part) = day1_part {
+        let options = day1_options.unwrap_or_default();
+        if part == 1 && options.calibrator.is_some() {
+            exit_with("--lang, --vocab and --compound are for part 2 only");
+        }
+        let input = args.get(2).cloned().map(open_filename_or_stdin);
+        match day1::run(part, input, &options) {
+            Ok(answer) => println!("{answer}"),
+            Err(err) => {
+                eprintln!("{err}");
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+    if let Some(options) = day2_options {
+        let part = match puzzle.strip_prefix("day").unwrap_or(&puzzle) {
+            "2.1" => 1,
+            "2.2" => 2,
+            _ => exit_with(&format!("{puzzle} doesn't take day 2's options")),
+        };
+        let input = args.get(2).cloned().map(open_filename_or_stdin);
+        match day2::run(part, input, &options) {
+            Ok(answer) => println!("{answer}"),
+            Err(err) => {
+                eprintln!("{err}");
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+    if let (Some(stream_solver), Some(filename)) =
+        (stream_solver(&puzzle), args.get(2).cloned())
+    {
+        println!("{}", stream_solver(open_filename_or_stdin(filename)));
+        return;
+    }
+    let solver = solver(&puzzle)
+        .unwrap_or_else(|| exit_with(&format!("unknown puzzle: {puzzle}")));
+    let input = args.get(2).cloned().map(read_from_filename_or_stdin);
+    println!("{}", solver(input));
 }
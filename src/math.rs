@@ -0,0 +1,276 @@
+//! Number theory helpers: gcd/lcm over any primitive integer width, plus
+//! extended Euclid, modular inverses and the Chinese Remainder Theorem for
+//! lining up cycles. Those last three only work on `i128`, which is wide
+//! enough for any product of cycle lengths we line up.
+
+use std::fmt::Debug;
+use std::ops::Div;
+
+/// The primitive integer types, for [`gcd`] and [`lcm`] and their variants.
+pub trait Integer: Copy + Debug + PartialEq + Div<Output = Self> {
+    const ZERO: Self;
+    /// Where [`lcm_all`] starts from.
+    #[cfg(test)]
+    const ONE: Self;
+
+    /// `None` for the one value whose magnitude doesn't fit, `MIN`.
+    fn checked_abs(self) -> Option<Self>;
+    /// The remainder, except that `MIN % -1` is 0 instead of overflowing.
+    fn wrapping_rem(self, other: Self) -> Self;
+    fn checked_mul(self, other: Self) -> Option<Self>;
+}
+
+macro_rules! impl_integer {
+    ($($t:ty),*; abs = $abs:ident) => {
+        $(
+            impl Integer for $t {
+                const ZERO: Self = 0;
+                #[cfg(test)]
+                const ONE: Self = 1;
+
+                fn checked_abs(self) -> Option<Self> {
+                    impl_integer!(@abs $abs self)
+                }
+
+                fn wrapping_rem(self, other: Self) -> Self {
+                    <$t>::wrapping_rem(self, other)
+                }
+
+                fn checked_mul(self, other: Self) -> Option<Self> {
+                    <$t>::checked_mul(self, other)
+                }
+            }
+        )*
+    };
+    (@abs signed $n:expr) => { $n.checked_abs() };
+    (@abs unsigned $n:expr) => { Some($n) };
+}
+
+impl_integer!(u8, u16, u32, u64, u128, usize; abs = unsigned);
+impl_integer!(i8, i16, i32, i64, i128, isize; abs = signed);
+
+/// The greatest common divisor, always non-negative, or `None` if it
+/// overflows `T`. That only happens for a signed `T` when the gcd is `MIN`'s
+/// magnitude, as in `gcd(MIN, 0)` or `gcd(MIN, MIN)`.
+pub fn checked_gcd<T: Integer>(a: T, b: T) -> Option<T> {
+    // the euclidean algorithm, which only gets the sign wrong on signed
+    // inputs, so that only the final value needs its magnitude taken
+    let (mut a, mut b) = (a, b);
+    while b != T::ZERO {
+        (a, b) = (b, a.wrapping_rem(b));
+    }
+    a.checked_abs()
+}
+
+/// The greatest common divisor, always non-negative.
+///
+/// # Panics
+///
+/// If the result overflows `T` (see [`checked_gcd`]).
+pub fn gcd<T: Integer>(a: T, b: T) -> T {
+    checked_gcd(a, b).unwrap_or_else(|| panic!("gcd({a:?}, {b:?}) overflows"))
+}
+
+/// The least common multiple, or `None` if it overflows `T`.
+pub fn checked_lcm<T: Integer>(a: T, b: T) -> Option<T> {
+    if a == T::ZERO || b == T::ZERO {
+        return Some(T::ZERO);
+    }
+    let g = checked_gcd(a, b)?;
+    (a / g).checked_abs()?.checked_mul(b.checked_abs()?)
+}
+
+/// The least common multiple.
+///
+/// # Panics
+///
+/// If the result overflows `T`.
+#[cfg(test)]
+pub fn lcm<T: Integer>(a: T, b: T) -> T {
+    checked_lcm(a, b).unwrap_or_else(|| panic!("lcm({a:?}, {b:?}) overflows"))
+}
+
+/// The least common multiple of all of `nums` (1 if there are none), or
+/// `None` if it overflows `T`. Day 8 lines its cycles up with [`crt`]
+/// instead, so this, [`lcm`] and [`lcm_all`] are only built for the tests.
+#[cfg(test)]
+pub fn checked_lcm_all<T: Integer>(
+    nums: impl IntoIterator<Item = T>,
+) -> Option<T> {
+    nums.into_iter()
+        .try_fold(T::ONE, |acc, n| checked_lcm(acc, n))
+}
+
+/// The least common multiple of all of `nums` (1 if there are none).
+///
+/// # Panics
+///
+/// If the result overflows `T`.
+#[cfg(test)]
+pub fn lcm_all<T: Integer>(nums: impl IntoIterator<Item = T>) -> T {
+    nums.into_iter().fold(T::ONE, lcm)
+}
+
+/// Returns `(g, x, y)` such that `a * x + b * y == g == gcd(a, b)`.
+pub fn extended_gcd(a: i128, b: i128) -> (i128, i128, i128) {
+    let (mut old_r, mut r) = (a, b);
+    let (mut old_x, mut x) = (1, 0);
+    let (mut old_y, mut y) = (0, 1);
+    while r != 0 {
+        let q = old_r / r;
+        (old_r, r) = (r, old_r - q * r);
+        (old_x, x) = (x, old_x - q * x);
+        (old_y, y) = (y, old_y - q * y);
+    }
+    if old_r < 0 {
+        (-old_r, -old_x, -old_y)
+    } else {
+        (old_r, old_x, old_y)
+    }
+}
+
+/// The `x` in `0..m` with `a * x ≡ 1 (mod m)`, or `None` if `a` and `m`
+/// aren't coprime.
+pub fn mod_inverse(a: i128, m: i128) -> Option<i128> {
+    assert!(m > 0, "modulus {m} is not positive");
+    let (g, x, _) = extended_gcd(a.rem_euclid(m), m);
+    (g == 1).then(|| x.rem_euclid(m))
+}
+
+/// Solves the system `x ≡ r (mod m)` for every `(r, m)` in `congruences`.
+///
+/// The moduli don't need to be coprime. Returns `(x, lcm)` with `x` the
+/// smallest non-negative solution and `lcm` the lcm of the moduli (every
+/// solution is `x + k * lcm`), or `None` if the congruences conflict or
+/// the lcm overflows an `i128`.
+///
+/// # Panics
+///
+/// If a modulus isn't positive.
+pub fn crt(congruences: &[(i128, i128)]) -> Option<(i128, i128)> {
+    congruences.iter().try_fold((0, 1), |(r1, m1), &(r2, m2)| {
+        assert!(m2 > 0, "modulus {m2} is not positive");
+        let r2 = r2.rem_euclid(m2);
+        let g = gcd(m1, m2);
+        if (r2 - r1) % g != 0 {
+            return None;
+        }
+        // m1 * k ≡ r2 - r1 (mod m2), solved after dividing through by g
+        let m2_g = m2 / g;
+        let inv = mod_inverse(m1 / g, m2_g).unwrap();
+        let k = mul_mod((r2 - r1) / g, inv, m2_g);
+        let m = checked_lcm(m1, m2)?;
+        // k < m2 / g, so m1 * k + r1 < m1 * (k + 1) <= m, but checked all
+        // the same
+        let x = m1.checked_mul(k)?.checked_add(r1)?;
+        Some((x % m, m))
+    })
+}
+
+/// `a * b mod m` without overflowing, for any `m` that fits in an `i128`.
+fn mul_mod(a: i128, b: i128, m: i128) -> i128 {
+    let (mut a, mut b) = (a.rem_euclid(m), b.rem_euclid(m));
+    if let Some(product) = a.checked_mul(b) {
+        return product % m;
+    }
+    // double and add
+    let mut product = 0;
+    while b > 0 {
+        if b & 1 == 1 {
+            product = (product + a) % m;
+        }
+        a = (a + a) % m;
+        b >>= 1;
+    }
+    product
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gcd() {
+        // from https://en.wikipedia.org/wiki/Greatest_common_divisor
+        assert_eq!(gcd(48, 18), 6);
+        assert_eq!(gcd(54, 24), 6);
+        assert_eq!(gcd(48, 180), 12);
+        assert_eq!(gcd(-48, 18), 6);
+        assert_eq!(gcd(0_u8, 7), 7);
+        assert_eq!(gcd(1_u128 << 100, 1 << 90), 1 << 90);
+        assert_eq!(gcd(i64::MIN, 6), 2);
+        assert_eq!(gcd(i8::MIN, -1), 1);
+        assert_eq!(checked_gcd(i8::MIN, 0), None);
+        assert_eq!(checked_gcd(0, i8::MIN), None);
+    }
+
+    #[test]
+    fn test_lcm() {
+        // from https://en.wikipedia.org/wiki/Least_common_multiple
+        assert_eq!(lcm_all([4, 6]), 12);
+        assert_eq!(lcm_all([21, 6]), 42);
+        assert_eq!(lcm_all([8, 9, 21]), 504);
+        assert_eq!(lcm_all([48, 180]), 720);
+        assert_eq!(lcm(-4, 6), 12);
+        assert_eq!(lcm_all::<u32>([]), 1);
+    }
+
+    #[test]
+    fn lcm_overflow() {
+        assert_eq!(checked_lcm(200_u8, 3), None);
+        assert_eq!(checked_lcm(200_u8, 4), Some(200));
+        assert_eq!(checked_lcm(i8::MIN, 1), None);
+        assert_eq!(checked_lcm(-64_i8, 2), Some(64));
+        assert_eq!(checked_lcm_all([1_u64 << 60, 3, 5 << 30, 7 << 20]), None);
+        assert_eq!(
+            checked_lcm_all([1_u128 << 60, 3, 5 << 30, 7 << 20]),
+            Some(105 << 60)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "overflows")]
+    fn lcm_overflow_panics() {
+        lcm(u64::MAX, u64::MAX - 1);
+    }
+
+    #[test]
+    fn test_extended_gcd() {
+        for (a, b) in [(240, 46), (46, 240), (-240, 46), (17, 0), (0, 5)] {
+            let (g, x, y) = extended_gcd(a, b);
+            assert_eq!(g, gcd(a, b));
+            assert_eq!(a * x + b * y, g);
+        }
+    }
+
+    #[test]
+    fn test_mod_inverse() {
+        assert_eq!(mod_inverse(3, 11), Some(4));
+        assert_eq!(mod_inverse(-3, 11), Some(7));
+        assert_eq!(mod_inverse(10, 17), Some(12));
+        assert_eq!(mod_inverse(6, 9), None);
+    }
+
+    #[test]
+    fn test_crt() {
+        // coprime moduli
+        assert_eq!(crt(&[(2, 3), (3, 5), (2, 7)]), Some((23, 105)));
+        // non-coprime but consistent
+        assert_eq!(crt(&[(2, 6), (8, 10)]), Some((8, 30)));
+        // non-coprime and conflicting
+        assert_eq!(crt(&[(1, 6), (2, 4)]), None);
+        // plain lcm alignment: every cycle hits its target at step 0
+        assert_eq!(crt(&[(0, 4), (0, 6)]), Some((0, 12)));
+        assert_eq!(crt(&[]), Some((0, 1)));
+        // moduli whose product overflows an i64
+        let (x, m) = crt(&[(1, 1 << 62), (2, (1 << 61) - 1)]).unwrap();
+        assert_eq!((x % (1 << 62), x % ((1 << 61) - 1)), (1, 2));
+        assert_eq!(m, (1 << 62) * ((1 << 61) - 1));
+        // coprime moduli whose lcm only just fits, and then doesn't
+        let big = (1 << 126) - 1;
+        let (x, m) = crt(&[(big - 1, big), (1, 2)]).unwrap();
+        assert_eq!((x, m), (2 * big - 1, 2 * big));
+        assert_eq!(crt(&[(0, big), (1, 3)]), None);
+        assert_eq!(crt(&[(0, 1 << 64), (1, u64::MAX as i128)]), None);
+    }
+}
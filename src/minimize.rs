@@ -0,0 +1,192 @@
+//! Shrinks a failing puzzle input down to a minimal reproducer.
+//!
+//! An input fails either by making the solver panic, identified by its
+//! panic signature (the panic location and message), or by making two
+//! independent implementations of the puzzle give different answers. Delta debugging
+//! removes input lines for as long as the input still fails the *same* way,
+//! so a candidate that merely breaks the day's parser (which panics
+//! somewhere else, or stops either implementation answering) is never
+//! accepted.
+
+use std::fs;
+use std::panic;
+use std::path::{Path, PathBuf};
+
+use crate::{catch, Solver};
+
+/// Runs `solver` on `input`, returning the panic signature if it panicked.
+fn failure(solver: Solver, input: &str) -> Option<String> {
+    catch::run(solver, Some(input.to_string())).err()
+}
+
+/// The answers `solver` and `second` give for `input`, if they both give
+/// one and the answers differ.
+fn disagreement(
+    solver: Solver,
+    second: Solver,
+    input: &str,
+) -> Option<(u64, u64)> {
+    let answer = catch::run(solver, Some(input.to_string())).ok()?;
+    let second_answer = catch::run(second, Some(input.to_string())).ok()?;
+    (answer != second_answer).then_some((answer, second_answer))
+}
+
+/// Classic `ddmin`: returns a 1-minimal subsequence of `input` for which
+/// `fails` still holds. `fails(input)` is assumed to be true.
+pub fn ddmin<T: Clone>(
+    input: &[T],
+    mut fails: impl FnMut(&[T]) -> bool,
+) -> Vec<T> {
+    let mut input = input.to_vec();
+    let mut n = 2;
+    while input.len() >= 2 {
+        let chunk_len = input.len().div_ceil(n);
+        let chunks: Vec<_> =
+            input.chunks(chunk_len).map(<[T]>::to_vec).collect();
+
+        if let Some(subset) = chunks.iter().find(|subset| fails(subset)) {
+            input = subset.clone();
+            n = 2;
+            continue;
+        }
+
+        let complement = (0..chunks.len()).find_map(|i| {
+            let complement = chunks
+                .iter()
+                .enumerate()
+                .filter(|&(j, _)| j != i)
+                .flat_map(|(_, chunk)| chunk.iter().cloned())
+                .collect::<Vec<_>>();
+            fails(&complement).then_some(complement)
+        });
+        if let Some(complement) = complement {
+            input = complement;
+            n = (n - 1).max(2);
+            continue;
+        }
+
+        if n >= input.len() {
+            break;
+        }
+        n = (2 * n).min(input.len());
+    }
+    input
+}
+
+/// Removes lines from `input` for as long as `fails` holds.
+fn shrink(input: &str, mut fails: impl FnMut(&str) -> bool) -> String {
+    let lines: Vec<_> = input.lines().collect();
+    let minimized = ddmin(&lines, |candidate| fails(&candidate.join("\n")));
+    eprintln!("minimized {} lines to {}", lines.len(), minimized.len());
+    minimized.join("\n") + "\n"
+}
+
+/// Writes `input` into `corpus_dir` as a new regression file for `puzzle`.
+fn save(puzzle: &str, input: &str, corpus_dir: &Path) -> PathBuf {
+    fs::create_dir_all(corpus_dir).unwrap();
+    let name = puzzle.strip_prefix("day").unwrap_or(puzzle);
+    let path = (1..)
+        .map(|i| corpus_dir.join(format!("day{name}-{i}.txt")))
+        .find(|path| !path.exists())
+        .unwrap();
+    fs::write(&path, input).unwrap();
+    path
+}
+
+/// Minimizes `input` for `solver` and writes the result into `corpus_dir`,
+/// returning the path of the new regression file, or `None` if `solver`
+/// doesn't fail on `input` in the first place.
+pub fn minimize(
+    puzzle: &str,
+    solver: Solver,
+    input: &str,
+    corpus_dir: &Path,
+) -> Option<PathBuf> {
+    catch::quiet_panics();
+    let signature = failure(solver, input);
+    let minimized = signature.map(|signature| {
+        eprintln!("failure: {signature}");
+        shrink(input, |candidate| {
+            failure(solver, candidate).as_ref() == Some(&signature)
+        })
+    });
+    // restore the default hook
+    drop(panic::take_hook());
+    Some(save(puzzle, &minimized?, corpus_dir))
+}
+
+/// Like [`minimize`], but for inputs where `solver` and `second`, another
+/// implementation of the same puzzle, give different answers.
+pub fn minimize_disagreement(
+    puzzle: &str,
+    solver: Solver,
+    second: Solver,
+    input: &str,
+    corpus_dir: &Path,
+) -> Option<PathBuf> {
+    catch::quiet_panics();
+    let minimized = disagreement(solver, second, input).map(|(a, b)| {
+        eprintln!("disagreement: {a} vs {b}");
+        shrink(input, |candidate| {
+            disagreement(solver, second, candidate).is_some()
+        })
+    });
+    drop(panic::take_hook());
+    Some(save(puzzle, &minimized?, corpus_dir))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ddmin_single_culprit() {
+        let input: Vec<_> = (0..100).collect();
+        assert_eq!(ddmin(&input, |c| c.contains(&42)), vec![42]);
+    }
+
+    #[test]
+    fn ddmin_interacting_culprits() {
+        let input: Vec<_> = (0..64).collect();
+        let minimized = ddmin(&input, |c| c.contains(&3) && c.contains(&60));
+        assert_eq!(minimized, vec![3, 60]);
+    }
+
+    #[test]
+    fn ddmin_keeps_order() {
+        let input = ["header", "a", "b", "c", "d"];
+        let minimized =
+            ddmin(&input, |c| c.first() == Some(&"header") && c.contains(&"c"));
+        assert_eq!(minimized, vec!["header", "c"]);
+    }
+
+    fn count_lines(input: Option<String>) -> u64 {
+        input.unwrap().lines().count() as u64
+    }
+
+    fn count_nonblank_lines(input: Option<String>) -> u64 {
+        input
+            .unwrap()
+            .lines()
+            .filter(|line| !line.is_empty())
+            .count() as u64
+    }
+
+    #[test]
+    fn shrinks_disagreements() {
+        let input = "a\nb\n\nc\nd";
+        assert_eq!(
+            disagreement(count_lines, count_nonblank_lines, input),
+            Some((5, 4))
+        );
+        assert_eq!(
+            disagreement(count_lines, count_nonblank_lines, "a\nb"),
+            None
+        );
+        // a blank line only counts as a line if something comes after it
+        let minimized = shrink(input, |candidate| {
+            disagreement(count_lines, count_nonblank_lines, candidate).is_some()
+        });
+        assert_eq!(minimized, "\nc\n");
+    }
+}
@@ -0,0 +1,261 @@
+//! Parsing helpers for the input shapes that keep coming up: `label: 1 2 3`
+//! lines, `key: value` headers, `a | b` halves and blank-line separated
+//! sections.
+//!
+//! Everything works on [`Span`]s, which are slices of the input that
+//! remember where they came from, so a [`ParseError`] can point at the line
+//! and column of the offending text.
+
+use std::fmt;
+use std::ops::Range;
+use std::str::FromStr;
+
+#[derive(Clone, PartialEq, Eq)]
+pub struct ParseError {
+    /// 1-based
+    pub line: usize,
+    /// 1-based, in characters; `None` for errors about a whole line
+    pub column: Option<usize>,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}", self.line)?;
+        if let Some(column) = self.column {
+            write!(f, ", column {column}")?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+// so that unwrap() on a parse result panics with a readable message
+impl fmt::Debug for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+pub type Result<T> = std::result::Result<T, ParseError>;
+
+/// A slice of some input text that knows its position in that input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Span<'a> {
+    root: &'a str,
+    text: &'a str,
+    /// Line number of the start of `root`, for inputs read line by line.
+    first_line: usize,
+}
+
+impl<'a> Span<'a> {
+    pub fn new(input: &'a str) -> Self {
+        Self::at_line(input, 1)
+    }
+
+    /// A span for text that starts on line `line` of some larger input.
+    pub fn at_line(text: &'a str, line: usize) -> Self {
+        Self {
+            root: text,
+            text,
+            first_line: line,
+        }
+    }
+
+    fn sub(&self, text: &'a str) -> Self {
+        Self { text, ..*self }
+    }
+
+    pub fn as_str(&self) -> &'a str {
+        self.text
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+
+    /// The 1-based line and column where this span starts.
+    pub fn position(&self) -> (usize, usize) {
+        let before = &self.root[..self.offset()];
+        let line = self.first_line + before.matches('\n').count();
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        (line, before[line_start..].chars().count() + 1)
+    }
+
+    pub fn error(&self, message: impl Into<String>) -> ParseError {
+        let (line, column) = self.position();
+        ParseError {
+            line,
+            column: Some(column),
+            message: message.into(),
+        }
+    }
+
+    /// An error about the whole line this span starts on, without a column.
+    pub fn line_error(&self, message: impl Into<String>) -> ParseError {
+        ParseError {
+            column: None,
+            ..self.error(message)
+        }
+    }
+
+    /// The part of this span at byte offsets `range` within it.
+    pub fn slice(&self, range: Range<usize>) -> Self {
+        self.sub(&self.text[range])
+    }
+
+    /// The empty span just past the end of this one.
+    pub fn end(&self) -> Self {
+        self.sub(&self.text[self.text.len()..])
+    }
+
+    pub fn trim(&self) -> Self {
+        self.sub(self.text.trim())
+    }
+
+    pub fn lines(&self) -> impl Iterator<Item = Span<'a>> + '_ {
+        self.text.lines().map(|line| self.sub(line))
+    }
+
+    /// Groups of lines separated by blank lines, without the blank lines.
+    pub fn sections(&self) -> impl Iterator<Item = Span<'a>> + '_ {
+        let mut lines = self.lines().peekable();
+        std::iter::from_fn(move || {
+            while lines.next_if(|line| line.trim().is_empty()).is_some() {}
+            let first = lines.next()?;
+            let mut last = first;
+            while let Some(line) = lines.next_if(|line| !line.trim().is_empty())
+            {
+                last = line;
+            }
+            let start = first.offset();
+            let end = last.offset() + last.text.len();
+            Some(self.sub(&self.root[start..end]))
+        })
+    }
+
+    fn offset(&self) -> usize {
+        self.text.as_ptr() as usize - self.root.as_ptr() as usize
+    }
+
+    pub fn split<'s>(
+        &'s self,
+        sep: &'s str,
+    ) -> impl Iterator<Item = Span<'a>> + 's {
+        self.text.split(sep).map(|part| self.sub(part))
+    }
+
+    pub fn words(&self) -> impl Iterator<Item = Span<'a>> + '_ {
+        self.text.split_whitespace().map(|word| self.sub(word))
+    }
+
+    pub fn split_once(&self, sep: &str) -> Result<(Span<'a>, Span<'a>)> {
+        let (a, b) = self
+            .text
+            .split_once(sep)
+            .ok_or_else(|| self.error(format!("expected `{sep}`")))?;
+        Ok((self.sub(a), self.sub(b)))
+    }
+
+    pub fn strip_prefix(&self, prefix: &str) -> Result<Span<'a>> {
+        self.text
+            .strip_prefix(prefix)
+            .map(|rest| self.sub(rest))
+            .ok_or_else(|| self.error(format!("expected `{prefix}`")))
+    }
+
+    /// Splits a `key: value` line, trimming both sides.
+    pub fn header(&self) -> Result<(Span<'a>, Span<'a>)> {
+        let (key, value) = self.split_once(":")?;
+        Ok((key.trim(), value.trim()))
+    }
+
+    /// The value of a `label: value` line, checking its label.
+    pub fn labeled(&self, label: &str) -> Result<Span<'a>> {
+        let (key, value) = self.header()?;
+        if key.text != label {
+            return Err(key.error(format!("expected `{label}`")));
+        }
+        Ok(value)
+    }
+
+    /// Parses the whole (trimmed) span as a number.
+    pub fn int<T: FromStr>(&self) -> Result<T> {
+        let text = self.trim();
+        text.text.parse().map_err(|_| {
+            text.error(format!(
+                "expected {}, found `{}`",
+                std::any::type_name::<T>(),
+                text.text
+            ))
+        })
+    }
+
+    /// Parses whitespace-separated numbers.
+    pub fn ints<T: FromStr>(&self) -> Result<Vec<T>> {
+        self.words().map(|word| word.int()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn labeled_ints() {
+        let input = Span::new("Time:      7  15   30\nDistance:  9  40  -200");
+        let mut lines = input.lines();
+        let time = lines.next().unwrap().labeled("Time").unwrap();
+        assert_eq!(time.ints::<u64>().unwrap(), vec![7, 15, 30]);
+        let distance = lines.next().unwrap().labeled("Distance").unwrap();
+        assert_eq!(distance.ints::<i64>().unwrap(), vec![9, 40, -200]);
+    }
+
+    #[test]
+    fn errors_point_at_the_problem() {
+        let input = Span::new("a: 1 2\nb: 3 x 5\n");
+        let line = input.lines().nth(1).unwrap();
+        let err = line.labeled("b").unwrap().ints::<u8>().unwrap_err();
+        assert_eq!((err.line, err.column), (2, Some(6)));
+        assert_eq!(err.message, "expected u8, found `x`");
+        assert_eq!(err.to_string(), "line 2, column 6: expected u8, found `x`");
+
+        let err = line.labeled("c").unwrap_err();
+        assert_eq!((err.line, err.column), (2, Some(1)));
+        assert_eq!(err.message, "expected `c`");
+
+        let err = input.lines().next().unwrap().split_once(" | ").unwrap_err();
+        assert_eq!(err.to_string(), "line 1, column 1: expected ` | `");
+
+        let err = Span::at_line("x", 10).int::<u32>().unwrap_err();
+        assert_eq!((err.line, err.column), (10, Some(1)));
+        let err = Span::at_line("x", 10).line_error("bad line");
+        assert_eq!(err.to_string(), "line 10: bad line");
+    }
+
+    #[test]
+    fn halves() {
+        let card = Span::new("Card 1: 41 48 | 83 86  6");
+        let (name, numbers) = card.header().unwrap();
+        assert_eq!(name.strip_prefix("Card ").unwrap().int::<u32>(), Ok(1));
+        let (winning, have) = numbers.split_once(" | ").unwrap();
+        assert_eq!(winning.ints::<u32>().unwrap(), vec![41, 48]);
+        assert_eq!(have.ints::<u32>().unwrap(), vec![83, 86, 6]);
+    }
+
+    #[test]
+    fn sections() {
+        let input =
+            Span::new("seeds: 1 2\n\na map:\n1 2 3\n4 5 6\n\n\nb map:\n7\n");
+        let sections: Vec<_> = input.sections().collect();
+        assert_eq!(sections.len(), 3);
+        assert_eq!(sections[1].as_str(), "a map:\n1 2 3\n4 5 6");
+        assert_eq!(sections[2].position(), (8, 1));
+        let ints = sections[2].lines().nth(1).unwrap().ints::<u8>();
+        assert_eq!(ints, Ok(vec![7]));
+        let (key, value) =
+            sections[1].lines().next().unwrap().header().unwrap();
+        assert_eq!((key.as_str(), value.as_str()), ("a map", ""));
+    }
+}
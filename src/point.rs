@@ -0,0 +1,222 @@
+//! Signed 2D points and compass directions, with `y` growing downwards (so
+//! north is `-y`), matching the row order of [`Grid`].
+//!
+//! [`Grid`]: crate::grid::Grid
+
+use std::ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Point {
+    pub x: isize,
+    pub y: isize,
+}
+
+impl Point {
+    #[cfg(test)]
+    pub const ORIGIN: Point = Point::new(0, 0);
+
+    /// Offsets to the orthogonal neighbours, clockwise from north.
+    #[cfg(test)]
+    pub const NEIGHBORS4: [Point; 4] = [
+        Point::new(0, -1),
+        Point::new(1, 0),
+        Point::new(0, 1),
+        Point::new(-1, 0),
+    ];
+
+    /// Offsets to the orthogonal and diagonal neighbours, clockwise from
+    /// north-west.
+    pub const NEIGHBORS8: [Point; 8] = [
+        Point::new(-1, -1),
+        Point::new(0, -1),
+        Point::new(1, -1),
+        Point::new(1, 0),
+        Point::new(1, 1),
+        Point::new(0, 1),
+        Point::new(-1, 1),
+        Point::new(-1, 0),
+    ];
+
+    pub const fn new(x: isize, y: isize) -> Self {
+        Self { x, y }
+    }
+
+    #[cfg(test)]
+    pub fn manhattan(self, other: Point) -> usize {
+        self.x.abs_diff(other.x) + self.y.abs_diff(other.y)
+    }
+
+    // like sparse_grid, which walks points this way, these are only
+    // built for the tests until a day needs them
+    #[cfg(test)]
+    pub fn neighbors4(self) -> impl Iterator<Item = Point> {
+        Point::NEIGHBORS4.into_iter().map(move |d| self + d)
+    }
+
+    #[cfg(test)]
+    pub fn neighbors8(self) -> impl Iterator<Item = Point> {
+        Point::NEIGHBORS8.into_iter().map(move |d| self + d)
+    }
+}
+
+impl From<(isize, isize)> for Point {
+    fn from((x, y): (isize, isize)) -> Self {
+        Self { x, y }
+    }
+}
+
+impl Add for Point {
+    type Output = Point;
+
+    fn add(self, other: Point) -> Point {
+        Point::new(self.x + other.x, self.y + other.y)
+    }
+}
+
+impl Sub for Point {
+    type Output = Point;
+
+    fn sub(self, other: Point) -> Point {
+        Point::new(self.x - other.x, self.y - other.y)
+    }
+}
+
+impl AddAssign for Point {
+    fn add_assign(&mut self, other: Point) {
+        *self = *self + other;
+    }
+}
+
+impl SubAssign for Point {
+    fn sub_assign(&mut self, other: Point) {
+        *self = *self - other;
+    }
+}
+
+impl Mul<isize> for Point {
+    type Output = Point;
+
+    fn mul(self, scale: isize) -> Point {
+        Point::new(self.x * scale, self.y * scale)
+    }
+}
+
+impl Neg for Point {
+    type Output = Point;
+
+    fn neg(self) -> Point {
+        Point::new(-self.x, -self.y)
+    }
+}
+
+impl Add<Direction> for Point {
+    type Output = Point;
+
+    fn add(self, dir: Direction) -> Point {
+        self + dir.delta()
+    }
+}
+
+impl AddAssign<Direction> for Point {
+    fn add_assign(&mut self, dir: Direction) {
+        *self = *self + dir;
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Direction {
+    North,
+    East,
+    South,
+    West,
+}
+
+impl Direction {
+    /// Clockwise from north.
+    pub const ALL: [Direction; 4] = [
+        Direction::North,
+        Direction::East,
+        Direction::South,
+        Direction::West,
+    ];
+
+    pub fn delta(self) -> Point {
+        match self {
+            Direction::North => Point::new(0, -1),
+            Direction::East => Point::new(1, 0),
+            Direction::South => Point::new(0, 1),
+            Direction::West => Point::new(-1, 0),
+        }
+    }
+
+    pub fn turn_right(self) -> Self {
+        match self {
+            Direction::North => Direction::East,
+            Direction::East => Direction::South,
+            Direction::South => Direction::West,
+            Direction::West => Direction::North,
+        }
+    }
+
+    #[cfg(test)]
+    pub fn turn_left(self) -> Self {
+        self.turn_around().turn_right()
+    }
+
+    pub fn turn_around(self) -> Self {
+        self.turn_right().turn_right()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn arithmetic() {
+        let p = Point::new(3, -2);
+        assert_eq!(p + Point::new(1, 1), Point::new(4, -1));
+        assert_eq!(p - Point::new(1, 1), Point::new(2, -3));
+        assert_eq!(p * 3, Point::new(9, -6));
+        assert_eq!(-p, Point::new(-3, 2));
+        assert_eq!(p + Direction::North, Point::new(3, -3));
+
+        let mut q = Point::ORIGIN;
+        q += Direction::East;
+        q += Point::new(0, 5);
+        q -= Point::new(2, 0);
+        assert_eq!(q, Point::new(-1, 5));
+        assert_eq!(Point::from((-1, 5)), q);
+    }
+
+    #[test]
+    fn manhattan() {
+        assert_eq!(Point::new(1, 1).manhattan(Point::new(-2, 5)), 7);
+        assert_eq!(Point::ORIGIN.manhattan(Point::ORIGIN), 0);
+    }
+
+    #[test]
+    fn turning() {
+        for dir in Direction::ALL {
+            assert_eq!(dir.turn_left().turn_right(), dir);
+            assert_eq!(dir.turn_around().turn_around(), dir);
+            assert_eq!(dir.turn_right().turn_right(), dir.turn_around());
+            assert_eq!(dir.delta() + dir.turn_around().delta(), Point::ORIGIN);
+        }
+        assert_eq!(Direction::North.turn_left(), Direction::West);
+        assert_eq!(Direction::West.turn_right(), Direction::North);
+    }
+
+    #[test]
+    fn direction_sets() {
+        let deltas: Vec<_> = Direction::ALL.map(Direction::delta).into();
+        assert_eq!(deltas, Point::NEIGHBORS4);
+        assert!(Point::NEIGHBORS4
+            .iter()
+            .all(|d| Point::NEIGHBORS8.contains(d)));
+        assert_eq!(Point::new(2, 2).neighbors8().count(), 8);
+        assert!(Point::new(2, 2)
+            .neighbors4()
+            .all(|p| p.manhattan(Point::new(2, 2)) == 1));
+    }
+}
@@ -0,0 +1,325 @@
+//! A minimal HTTP/1.1 front end for the solvers, listening on localhost only.
+//!
+//! - `GET /puzzles` lists every implemented puzzle.
+//! - `POST /solve/{day}/{part}` solves the puzzle with the request body as
+//!   input (or the example input if the body is empty).
+//!
+//! Connections are handled one at a time and closed after each response.
+//! Lines over [`MAX_LINE`] bytes, more than [`MAX_HEADERS`] headers and
+//! bodies over [`MAX_BODY`] bytes are refused, and a client gets
+//! [`DEADLINE`] to send its whole request, however it spaces it out, so no
+//! client can grow the server's memory or stall it.
+
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::time::{Duration, Instant};
+
+use crate::{catch, solver, PUZZLES};
+
+/// The largest input accepted, well above any real puzzle input.
+const MAX_BODY: usize = 16 << 20;
+/// The longest request line or header accepted.
+const MAX_LINE: u64 = 4 << 10;
+const MAX_HEADERS: usize = 64;
+const DEADLINE: Duration = Duration::from_secs(10);
+
+pub fn serve(port: u16) {
+    let listener = TcpListener::bind(("127.0.0.1", port)).unwrap();
+    eprintln!("listening on http://{}", listener.local_addr().unwrap());
+    catch::quiet_panics();
+    for stream in listener.incoming() {
+        if let Err(e) = stream.and_then(handle_connection) {
+            eprintln!("connection error: {e}");
+        }
+    }
+}
+
+/// Reads from a connection until `deadline`, which no amount of trickling
+/// bytes can push back.
+struct Deadline<'a> {
+    stream: &'a TcpStream,
+    deadline: Instant,
+}
+
+impl Read for Deadline<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let left = self.deadline.saturating_duration_since(Instant::now());
+        if left.is_zero() {
+            return Err(io::ErrorKind::TimedOut.into());
+        }
+        self.stream.set_read_timeout(Some(left))?;
+        self.stream.read(buf)
+    }
+}
+
+fn handle_connection(stream: TcpStream) -> io::Result<()> {
+    let mut reader = BufReader::new(Deadline {
+        stream: &stream,
+        deadline: Instant::now() + DEADLINE,
+    });
+    let (status, json) = handle_request(&mut reader)?;
+    write!(
+        &stream,
+        concat!(
+            "HTTP/1.1 {}\r\n",
+            "Content-Type: application/json\r\n",
+            "Content-Length: {}\r\n",
+            "Connection: close\r\n",
+            "\r\n",
+            "{}"
+        ),
+        status,
+        json.len(),
+        json
+    )
+}
+
+/// Reads a line of up to [`MAX_LINE`] bytes, or `None` if it's longer.
+fn read_line(reader: &mut impl BufRead) -> io::Result<Option<String>> {
+    let mut line = String::new();
+    reader.take(MAX_LINE).read_line(&mut line)?;
+    let whole = line.ends_with('\n') || line.len() < MAX_LINE as usize;
+    Ok(whole.then_some(line))
+}
+
+fn handle_request(
+    reader: &mut impl BufRead,
+) -> io::Result<(&'static str, String)> {
+    let Some(request_line) = read_line(reader)? else {
+        return Ok(("400 Bad Request", error("request line too long")));
+    };
+    let mut request = request_line.split_whitespace();
+    let method = request.next().unwrap_or_default();
+    let path = request.next().unwrap_or_default();
+
+    let mut content_length = Some(0);
+    for headers in 0.. {
+        let Some(header) = read_line(reader)? else {
+            return Ok((
+                "431 Request Header Fields Too Large",
+                error("header too long"),
+            ));
+        };
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if headers == MAX_HEADERS {
+            return Ok((
+                "431 Request Header Fields Too Large",
+                error("too many headers"),
+            ));
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse().ok();
+            }
+        }
+    }
+    Ok(match content_length {
+        None => ("400 Bad Request", error("invalid Content-Length")),
+        Some(length) if length > MAX_BODY => (
+            "413 Content Too Large",
+            error(&format!("input is over {MAX_BODY} bytes")),
+        ),
+        Some(length) => {
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body)?;
+            respond(method, path, body)
+        }
+    })
+}
+
+fn respond(method: &str, path: &str, body: Vec<u8>) -> (&'static str, String) {
+    let segments: Vec<_> = path.trim_matches('/').split('/').collect();
+    match (method, &segments[..]) {
+        ("GET", ["puzzles"]) => ("200 OK", puzzles()),
+        ("POST", ["solve", day, part]) => solve(day, part, body),
+        (_, ["puzzles"]) | (_, ["solve", _, _]) => {
+            ("405 Method Not Allowed", error("method not allowed"))
+        }
+        _ => ("404 Not Found", error("not found")),
+    }
+}
+
+fn puzzles() -> String {
+    let puzzles = PUZZLES
+        .iter()
+        .map(|(name, _)| {
+            let (day, part) = name.split_once('.').unwrap();
+            format!(r#"{{"day":{day},"part":{part}}}"#)
+        })
+        .collect::<Vec<_>>()
+        .join(",");
+    format!(r#"{{"puzzles":[{puzzles}]}}"#)
+}
+
+fn solve(day: &str, part: &str, body: Vec<u8>) -> (&'static str, String) {
+    // day and part are echoed into the JSON, so only take numbers
+    let numbers = (day.parse::<u32>(), part.parse::<u32>());
+    let (Ok(day), Ok(part)) = numbers else {
+        return ("404 Not Found", error("unknown puzzle"));
+    };
+    let Some(solver) = solver(&format!("{day}.{part}")) else {
+        return ("404 Not Found", error("unknown puzzle"));
+    };
+    let input = match String::from_utf8(body) {
+        Ok(body) if body.is_empty() => None,
+        Ok(body) => Some(body),
+        Err(_) => return ("400 Bad Request", error("input is not UTF-8")),
+    };
+
+    let start = Instant::now();
+    let result = catch::run(solver, input);
+    let elapsed_us = start.elapsed().as_micros();
+    match result {
+        Ok(answer) => (
+            "200 OK",
+            format!(
+                r#"{{"day":{day},"part":{part},"answer":{answer},"elapsed_us":{elapsed_us}}}"#
+            ),
+        ),
+        Err(message) => (
+            "422 Unprocessable Entity",
+            format!(
+                r#"{{"day":{day},"part":{part},"error":{},"elapsed_us":{elapsed_us}}}"#,
+                json_string(&message)
+            ),
+        ),
+    }
+}
+
+fn error(message: &str) -> String {
+    format!(r#"{{"error":{}}}"#, json_string(message))
+}
+
+fn json_string(s: &str) -> String {
+    let mut json = String::from('"');
+    for c in s.chars() {
+        match c {
+            '"' => json.push_str(r#"\""#),
+            '\\' => json.push_str(r"\\"),
+            '\n' => json.push_str(r"\n"),
+            '\r' => json.push_str(r"\r"),
+            '\t' => json.push_str(r"\t"),
+            c if (c as u32) < 0x20 => {
+                json.push_str(&format!(r"\u{:04x}", c as u32))
+            }
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn list_puzzles() {
+        let (status, json) = respond("GET", "/puzzles", vec![]);
+        assert_eq!(status, "200 OK");
+        assert!(json.starts_with(r#"{"puzzles":[{"day":0,"part":1},"#));
+    }
+
+    #[test]
+    fn solve_example() {
+        let (status, json) = respond("POST", "/solve/6/1", vec![]);
+        assert_eq!(status, "200 OK");
+        assert!(json.starts_with(r#"{"day":6,"part":1,"answer":288,"#));
+    }
+
+    #[test]
+    fn solve_errors() {
+        let (status, _) = respond("POST", "/solve/99/1", vec![]);
+        assert_eq!(status, "404 Not Found");
+        let (status, _) = respond("POST", "/solve/day6/1", vec![]);
+        assert_eq!(status, "404 Not Found");
+        let (status, _) = respond("GET", "/solve/6/1", vec![]);
+        assert_eq!(status, "405 Method Not Allowed");
+        let (status, json) =
+            respond("POST", "/solve/0/1", b"not a number".to_vec());
+        assert_eq!(status, "422 Unprocessable Entity");
+        assert!(json.contains(r#""error":"#));
+    }
+
+    #[test]
+    fn json_escaping() {
+        assert_eq!(json_string("a \"b\"\n\\"), r#""a \"b\"\n\\""#);
+    }
+
+    /// Sends `request` to a server handling one connection, and returns its
+    /// response.
+    fn exchange(request: &str) -> String {
+        let listener = TcpListener::bind(("127.0.0.1", 0)).unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = std::thread::spawn(move || {
+            handle_connection(listener.accept().unwrap().0).unwrap();
+        });
+
+        let mut client = TcpStream::connect(addr).unwrap();
+        client.write_all(request.as_bytes()).unwrap();
+        let mut response = String::new();
+        client.read_to_string(&mut response).unwrap();
+        server.join().unwrap();
+        response
+    }
+
+    #[test]
+    fn over_http() {
+        let input = "Time:      7  15   30\nDistance:  9  40  200\n";
+        let response = exchange(&format!(
+            "POST /solve/6/2 HTTP/1.1\r\nContent-Length: {}\r\n\r\n{}",
+            input.len(),
+            input
+        ));
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.contains(r#""answer":71503"#));
+    }
+
+    #[test]
+    fn bad_content_length() {
+        let response =
+            exchange("POST /solve/6/2 HTTP/1.1\r\nContent-Length: x\r\n\r\n");
+        assert!(response.starts_with("HTTP/1.1 400 Bad Request\r\n"));
+        let response = exchange(&format!(
+            "POST /solve/6/2 HTTP/1.1\r\nContent-Length: {}\r\n\r\n",
+            MAX_BODY + 1
+        ));
+        assert!(response.starts_with("HTTP/1.1 413 Content Too Large\r\n"));
+    }
+
+    #[test]
+    fn oversized_requests() {
+        let long = "x".repeat(MAX_LINE as usize);
+        let response = exchange(&format!("GET /{long} HTTP/1.1\r\n\r\n"));
+        assert!(response.starts_with("HTTP/1.1 400 Bad Request\r\n"));
+
+        let too_large = "HTTP/1.1 431 Request Header Fields Too Large\r\n";
+        let response =
+            exchange(&format!("GET /puzzles HTTP/1.1\r\nX: {long}\r\n\r\n"));
+        assert!(response.starts_with(too_large));
+        let headers = "X: y\r\n".repeat(MAX_HEADERS + 1);
+        let response =
+            exchange(&format!("GET /puzzles HTTP/1.1\r\n{headers}\r\n"));
+        assert!(response.starts_with(too_large));
+        let headers = "X: y\r\n".repeat(MAX_HEADERS);
+        let response =
+            exchange(&format!("GET /puzzles HTTP/1.1\r\n{headers}\r\n"));
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+    }
+
+    #[test]
+    fn deadline_covers_the_whole_request() {
+        let listener = TcpListener::bind(("127.0.0.1", 0)).unwrap();
+        let _client = TcpStream::connect(listener.local_addr().unwrap());
+        let stream = listener.accept().unwrap().0;
+        let mut reader = Deadline {
+            stream: &stream,
+            deadline: Instant::now(),
+        };
+        let err = reader.read(&mut [0; 1]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+    }
+}
@@ -0,0 +1,190 @@
+//! An unbounded 2D grid that only stores occupied cells, for puzzles whose
+//! coordinates go negative or grow too large for a dense [`Grid`].
+//!
+//! Positions are signed [`Point`]s with `y` growing downwards, as in
+//! [`Grid`]. The grid tracks the bounding box of its occupied cells, which is
+//! also the region [`SparseGrid::render`] draws.
+//!
+//! No day's coordinates go negative or unbounded yet, so main.rs only
+//! builds this module for its tests.
+//!
+//! [`Grid`]: crate::grid::Grid
+
+use std::collections::HashMap;
+
+use crate::point::Point;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SparseGrid<T> {
+    cells: HashMap<Point, T>,
+    /// Inclusive top-left and bottom-right corners of the occupied cells.
+    bounds: Option<(Point, Point)>,
+}
+
+/// Grows `bounds` just enough to include `p`.
+fn extend(bounds: Option<(Point, Point)>, p: Point) -> (Point, Point) {
+    match bounds {
+        None => (p, p),
+        Some((min, max)) => (
+            Point::new(min.x.min(p.x), min.y.min(p.y)),
+            Point::new(max.x.max(p.x), max.y.max(p.y)),
+        ),
+    }
+}
+
+impl<T> Default for SparseGrid<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> SparseGrid<T> {
+    pub fn new() -> Self {
+        Self {
+            cells: HashMap::new(),
+            bounds: None,
+        }
+    }
+
+    /// Parses one row per line, keeping the characters that `cell` converts
+    /// to `Some`.
+    pub fn parse(input: &str, mut cell: impl FnMut(char) -> Option<T>) -> Self {
+        let mut grid = Self::new();
+        for (y, line) in input.lines().enumerate() {
+            for (x, c) in line.chars().enumerate() {
+                if let Some(value) = cell(c) {
+                    grid.insert(x as isize, y as isize, value);
+                }
+            }
+        }
+        grid
+    }
+
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    pub fn contains(&self, x: isize, y: isize) -> bool {
+        self.cells.contains_key(&Point::new(x, y))
+    }
+
+    pub fn get(&self, x: isize, y: isize) -> Option<&T> {
+        self.cells.get(&Point::new(x, y))
+    }
+
+    pub fn get_mut(&mut self, x: isize, y: isize) -> Option<&mut T> {
+        self.cells.get_mut(&Point::new(x, y))
+    }
+
+    pub fn insert(&mut self, x: isize, y: isize, value: T) -> Option<T> {
+        let p = Point::new(x, y);
+        self.bounds = Some(extend(self.bounds, p));
+        self.cells.insert(p, value)
+    }
+
+    pub fn remove(&mut self, x: isize, y: isize) -> Option<T> {
+        let removed = self.cells.remove(&Point::new(x, y))?;
+        let (min, max) = self.bounds.unwrap();
+        if x == min.x || x == max.x || y == min.y || y == max.y {
+            // the box may have shrunk
+            self.bounds = self
+                .cells
+                .keys()
+                .fold(None, |bounds, &pos| Some(extend(bounds, pos)));
+        }
+        Some(removed)
+    }
+
+    /// The inclusive top-left and bottom-right corners of the occupied cells,
+    /// or `None` if the grid is empty.
+    pub fn bounds(&self) -> Option<(Point, Point)> {
+        self.bounds
+    }
+
+    /// Every occupied position along with its cell, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = (Point, &T)> {
+        self.cells.iter().map(|(&pos, cell)| (pos, cell))
+    }
+
+    /// The orthogonal neighbours of `(x, y)`, occupied or not. Unlike a
+    /// dense grid's, these don't depend on the grid.
+    pub fn neighbors4(x: isize, y: isize) -> impl Iterator<Item = Point> {
+        Point::new(x, y).neighbors4()
+    }
+
+    /// The orthogonal and diagonal neighbours of `(x, y)`, occupied or not.
+    pub fn neighbors8(x: isize, y: isize) -> impl Iterator<Item = Point> {
+        Point::new(x, y).neighbors8()
+    }
+
+    /// Draws the bounding box one line per row, using `cell` to pick the
+    /// character for each position (`None` for unoccupied ones).
+    pub fn render(&self, mut cell: impl FnMut(Option<&T>) -> char) -> String {
+        let Some((min, max)) = self.bounds else {
+            return String::new();
+        };
+        let mut text = String::new();
+        for y in min.y..=max.y {
+            text.extend((min.x..=max.x).map(|x| cell(self.get(x, y))));
+            text.push('\n');
+        }
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bounds_follow_occupied_cells() {
+        let mut grid = SparseGrid::new();
+        assert_eq!(grid.bounds(), None);
+        grid.insert(0, 0, 'a');
+        grid.insert(-3, 2, 'b');
+        grid.insert(1_000_000, -5, 'c');
+        assert_eq!(
+            grid.bounds(),
+            Some((Point::new(-3, -5), Point::new(1_000_000, 2)))
+        );
+        assert_eq!(grid.len(), 3);
+        *grid.get_mut(-3, 2).unwrap() = 'B';
+        let mut cells: Vec<_> = grid.iter().map(|(_, &c)| c).collect();
+        cells.sort();
+        assert_eq!(cells, ['B', 'a', 'c']);
+
+        assert_eq!(grid.remove(1_000_000, -5), Some('c'));
+        assert_eq!(grid.bounds(), Some((Point::new(-3, 0), Point::new(0, 2))));
+        assert_eq!(grid.remove(1_000_000, -5), None);
+        grid.remove(0, 0);
+        grid.remove(-3, 2);
+        assert_eq!(grid.bounds(), None);
+        assert!(grid.is_empty());
+    }
+
+    #[test]
+    fn parse_and_render() {
+        let mut grid =
+            SparseGrid::parse("#..\n..#\n", |c| (c == '#').then_some(()));
+        assert_eq!(grid.len(), 2);
+        assert!(grid.contains(2, 1));
+        grid.insert(-1, -1, ());
+        let render = |c: Option<&()>| if c.is_some() { '#' } else { '.' };
+        assert_eq!(grid.render(render), "#...\n.#..\n...#\n");
+    }
+
+    #[test]
+    fn neighbors() {
+        type Grid = SparseGrid<()>;
+        assert_eq!(
+            Grid::neighbors4(0, 0).collect::<Vec<_>>(),
+            Point::NEIGHBORS4
+        );
+        assert_eq!(Grid::neighbors8(-5, 7).count(), 8);
+        assert!(Grid::neighbors8(-5, 7).all(|p| p != Point::new(-5, 7)));
+    }
+}
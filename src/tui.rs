@@ -0,0 +1,565 @@
+//! A terminal dashboard for the whole calendar: shows every day's status, its
+//! last answers, timings and explain traces, and runs puzzles on demand
+//! against either the example input or the real input in `inputs/dayN.txt`.
+//!
+//! Puzzles run on a worker thread, so the dashboard stays responsive while a
+//! slow one works. An answer to the example input is checked against the
+//! example's known answer; an answer to real input can't be, so a day run on
+//! real input only counts as "ran".
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::{self, Write};
+use std::panic;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use crossterm::style::{Color, Print, ResetColor, SetForegroundColor};
+use crossterm::{cursor, execute, queue, terminal};
+
+use crate::explain::{self, Trace};
+use crate::{catch, PUZZLES};
+
+const DAYS: usize = 25;
+const COLUMNS: usize = 5;
+/// Trace steps shown per part; the rest are summarised.
+const TRACE_LINES: usize = 10;
+
+/// The answers to each puzzle's example input.
+const EXAMPLE_ANSWERS: &[(&str, u64)] = &[
+    ("0.1", 69),
+    ("0.2", 420),
+    ("1.1", 142),
+    ("1.2", 281),
+    ("2.1", 8),
+    ("2.2", 2286),
+    ("3.1", 4361),
+    ("3.2", 467835),
+    ("4.1", 13),
+    ("4.2", 30),
+    ("5.1", 35),
+    ("5.2", 46),
+    ("6.1", 288),
+    ("6.2", 71503),
+    ("7.1", 6440),
+    ("7.2", 5905),
+    ("8.1", 6),
+    ("8.2", 6),
+    ("9.1", 114),
+    ("9.2", 2),
+    ("10.1", 8),
+];
+
+fn example_answer(day: usize, part: usize) -> Option<u64> {
+    let name = format!("{day}.{part}");
+    EXAMPLE_ANSWERS
+        .iter()
+        .find(|&&(puzzle, _)| puzzle == name)
+        .map(|&(_, answer)| answer)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum InputKind {
+    Example,
+    Real,
+}
+
+impl InputKind {
+    fn toggle(self) -> Self {
+        match self {
+            InputKind::Example => InputKind::Real,
+            InputKind::Real => InputKind::Example,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Status {
+    NotImplemented,
+    Unsolved,
+    Running,
+    Failing,
+    /// Every part ran, but not every answer could be checked.
+    Ran,
+    /// Every part gave the example's known answer.
+    Solved,
+}
+
+impl Status {
+    fn label(self) -> &'static str {
+        match self {
+            Status::NotImplemented => "  -  ",
+            Status::Unsolved => "  ?  ",
+            Status::Running => " ... ",
+            Status::Failing => "  x  ",
+            Status::Ran => "  o  ",
+            Status::Solved => "  *  ",
+        }
+    }
+
+    fn color(self) -> Color {
+        match self {
+            Status::NotImplemented => Color::DarkGrey,
+            Status::Unsolved => Color::White,
+            Status::Running => Color::Cyan,
+            Status::Failing => Color::Red,
+            Status::Ran => Color::Green,
+            Status::Solved => Color::Yellow,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Run {
+    input: InputKind,
+    outcome: Result<u64, String>,
+    /// The known answer, for example input.
+    expected: Option<u64>,
+    elapsed: Duration,
+    trace: Trace,
+}
+
+impl Run {
+    fn is_wrong(&self) -> bool {
+        match (&self.outcome, self.expected) {
+            (Err(_), _) => true,
+            (Ok(answer), Some(expected)) => *answer != expected,
+            (Ok(_), None) => false,
+        }
+    }
+}
+
+fn parts(day: usize) -> Vec<usize> {
+    PUZZLES
+        .iter()
+        .filter_map(|(name, _)| {
+            let (d, p) = name.split_once('.')?;
+            (d.parse() == Ok(day)).then(|| p.parse().unwrap())
+        })
+        .collect()
+}
+
+/// Runs every part of `day`, recording each part's trace.
+fn run_day(day: usize, kind: InputKind) -> Vec<(usize, Run)> {
+    let input = match kind {
+        InputKind::Example => Ok(None),
+        InputKind::Real => fs::read_to_string(format!("inputs/day{day}.txt"))
+            .map(Some)
+            .map_err(|e| format!("inputs/day{day}.txt: {e}")),
+    };
+    let mut runs = Vec::new();
+    for (name, solver) in PUZZLES {
+        let Some((d, p)) = name.split_once('.') else {
+            continue;
+        };
+        if d.parse() != Ok(day) {
+            continue;
+        }
+        let part = p.parse().unwrap();
+        let start = Instant::now();
+        let (outcome, trace) = explain::record(|| {
+            input.clone().and_then(|input| catch::run(*solver, input))
+        });
+        let run = Run {
+            input: kind,
+            outcome,
+            expected: match kind {
+                InputKind::Example => example_answer(day, part),
+                InputKind::Real => None,
+            },
+            elapsed: start.elapsed(),
+            trace,
+        };
+        runs.push((part, run));
+    }
+    runs
+}
+
+/// A day to run, and on which input.
+type Job = (usize, InputKind);
+/// A day that ran, and its runs by part.
+type Finished = (usize, Vec<(usize, Run)>);
+
+/// Runs days on a background thread, handing back each day's runs.
+fn spawn_worker() -> (Sender<Job>, Receiver<Finished>) {
+    let (jobs, job_rx) = mpsc::channel::<Job>();
+    let (result_tx, results) = mpsc::channel();
+    thread::spawn(move || {
+        for (day, kind) in job_rx {
+            if result_tx.send((day, run_day(day, kind))).is_err() {
+                break;
+            }
+        }
+    });
+    (jobs, results)
+}
+
+#[derive(Debug)]
+struct Dashboard {
+    selected: usize,
+    input: InputKind,
+    explain: bool,
+    runs: HashMap<(usize, usize), Run>,
+    running: HashSet<usize>,
+    jobs: Sender<Job>,
+    results: Receiver<Finished>,
+}
+
+impl Dashboard {
+    fn new() -> Self {
+        let (jobs, results) = spawn_worker();
+        Self {
+            selected: 1,
+            input: InputKind::Example,
+            explain: false,
+            runs: HashMap::new(),
+            running: HashSet::new(),
+            jobs,
+            results,
+        }
+    }
+
+    fn status(&self, day: usize) -> Status {
+        let parts = parts(day);
+        if parts.is_empty() {
+            return Status::NotImplemented;
+        }
+        if self.running.contains(&day) {
+            return Status::Running;
+        }
+        let runs: Vec<_> = parts
+            .iter()
+            .filter_map(|&part| self.runs.get(&(day, part)))
+            .collect();
+        if runs.iter().any(|run| run.is_wrong()) {
+            Status::Failing
+        } else if runs.len() < parts.len() {
+            Status::Unsolved
+        } else if runs.iter().all(|run| run.expected.is_some()) {
+            Status::Solved
+        } else {
+            Status::Ran
+        }
+    }
+
+    fn move_by(&mut self, dx: isize, dy: isize) {
+        let index = self.selected as isize - 1 + dx + dy * COLUMNS as isize;
+        if (0..DAYS as isize).contains(&index) {
+            self.selected = index as usize + 1;
+        }
+    }
+
+    /// Queues `day` to run, unless it has nothing to run or already is.
+    fn run(&mut self, day: usize) {
+        if parts(day).is_empty() || !self.running.insert(day) {
+            return;
+        }
+        // the worker only stops once the dashboard is gone
+        self.jobs.send((day, self.input)).unwrap();
+    }
+
+    /// Takes in the runs of days that have finished, waiting for at least
+    /// one if `wait`. Returns whether anything changed.
+    fn collect(&mut self, wait: bool) -> bool {
+        let mut changed = false;
+        if wait && !self.running.is_empty() {
+            let finished = self.results.recv().unwrap();
+            self.finish(finished);
+            changed = true;
+        }
+        while let Ok(finished) = self.results.try_recv() {
+            self.finish(finished);
+            changed = true;
+        }
+        changed
+    }
+
+    fn finish(&mut self, (day, runs): Finished) {
+        self.running.remove(&day);
+        for (part, run) in runs {
+            self.runs.insert((day, part), run);
+        }
+    }
+
+    fn cell(&self, day: usize) -> String {
+        let (open, close) = if day == self.selected {
+            ('[', ']')
+        } else {
+            (' ', ' ')
+        };
+        format!("{open}{day:>2}{}{close} ", self.status(day).label())
+    }
+
+    fn details(&self) -> Vec<String> {
+        let day = self.selected;
+        let mut lines = vec![format!("Day {day}: {:?}", self.status(day))];
+        for part in parts(day) {
+            let Some(run) = self.runs.get(&(day, part)) else {
+                lines.push(format!("  part {part}: not run"));
+                continue;
+            };
+            let outcome = match (&run.outcome, run.expected) {
+                (Ok(answer), Some(expected)) if *answer != expected => {
+                    format!("{answer}, expected {expected}")
+                }
+                (Ok(answer), _) => answer.to_string(),
+                (Err(e), _) => format!("error: {e}"),
+            };
+            lines.push(format!(
+                "  part {part}: {outcome} ({:?} input, {:.3?})",
+                run.input, run.elapsed
+            ));
+            if self.explain {
+                lines.extend(trace_lines(&run.trace));
+            }
+        }
+        lines
+    }
+
+    fn draw(&self, out: &mut impl Write) -> io::Result<()> {
+        queue!(
+            out,
+            terminal::Clear(terminal::ClearType::All),
+            cursor::MoveTo(0, 0),
+            SetForegroundColor(Color::Green),
+            Print(format!("Advent of Code 2023 -- input: {:?}", self.input)),
+            ResetColor,
+        )?;
+        let mut y = 2;
+        for row in 0..DAYS / COLUMNS {
+            queue!(out, cursor::MoveTo(0, y))?;
+            for day in row * COLUMNS + 1..=(row + 1) * COLUMNS {
+                queue!(
+                    out,
+                    SetForegroundColor(self.status(day).color()),
+                    Print(self.cell(day)),
+                    ResetColor
+                )?;
+            }
+            y += 1;
+        }
+        y += 1;
+        let color = self.status(self.selected).color();
+        for (i, line) in self.details().into_iter().enumerate() {
+            let color = if i == 0 { color } else { Color::Reset };
+            queue!(
+                out,
+                cursor::MoveTo(0, y),
+                SetForegroundColor(color),
+                Print(line),
+                ResetColor
+            )?;
+            y += 1;
+        }
+        queue!(
+            out,
+            cursor::MoveTo(0, y + 1),
+            SetForegroundColor(Color::DarkGrey),
+            Print(concat!(
+                "arrows/hjkl: select  enter: run day  a: run all  ",
+                "i: toggle input  e: explain  q: quit"
+            )),
+            ResetColor
+        )?;
+        out.flush()
+    }
+}
+
+/// The first few steps of a trace, indented under its part.
+fn trace_lines(trace: &Trace) -> Vec<String> {
+    if trace.steps.is_empty() {
+        return vec!["    (no trace for this puzzle)".to_string()];
+    }
+    let mut lines: Vec<_> = trace
+        .steps
+        .iter()
+        .take(TRACE_LINES)
+        .map(|step| format!("    {step}"))
+        .collect();
+    let more = trace.steps.len().saturating_sub(TRACE_LINES) + trace.dropped;
+    if more > 0 {
+        lines.push(format!("    ... and {more} more steps"));
+    }
+    lines
+}
+
+pub fn tui() -> io::Result<()> {
+    let mut dashboard = Dashboard::new();
+    let mut out = io::stdout();
+    // a panic outside the solvers, which `catch` reports on the dashboard,
+    // would otherwise be printed to the alternate screen and lost with it,
+    // leaving the terminal raw
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        let _ = execute!(
+            io::stdout(),
+            cursor::Show,
+            terminal::LeaveAlternateScreen
+        );
+        let _ = terminal::disable_raw_mode();
+        default_hook(info);
+    }));
+    catch::quiet_panics();
+    terminal::enable_raw_mode()?;
+    execute!(out, terminal::EnterAlternateScreen, cursor::Hide)?;
+
+    let result = (|| {
+        dashboard.draw(&mut out)?;
+        loop {
+            // check on running puzzles every so often between key presses
+            if !event::poll(Duration::from_millis(100))? {
+                if dashboard.collect(false) {
+                    dashboard.draw(&mut out)?;
+                }
+                continue;
+            }
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                KeyCode::Left | KeyCode::Char('h') => dashboard.move_by(-1, 0),
+                KeyCode::Right | KeyCode::Char('l') => dashboard.move_by(1, 0),
+                KeyCode::Up | KeyCode::Char('k') => dashboard.move_by(0, -1),
+                KeyCode::Down | KeyCode::Char('j') => dashboard.move_by(0, 1),
+                KeyCode::Char('i') => {
+                    dashboard.input = dashboard.input.toggle()
+                }
+                KeyCode::Char('e') => dashboard.explain = !dashboard.explain,
+                KeyCode::Enter => dashboard.run(dashboard.selected),
+                KeyCode::Char('a') => {
+                    (1..=DAYS).for_each(|day| dashboard.run(day))
+                }
+                _ => {}
+            }
+            dashboard.collect(false);
+            dashboard.draw(&mut out)?;
+        }
+    })();
+
+    execute!(out, cursor::Show, terminal::LeaveAlternateScreen)?;
+    terminal::disable_raw_mode()?;
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Runs `day` and waits for it to finish.
+    fn run_now(dashboard: &mut Dashboard, day: usize) {
+        dashboard.run(day);
+        assert_eq!(dashboard.status(day), Status::Running);
+        while dashboard.running.contains(&day) {
+            dashboard.collect(true);
+        }
+    }
+
+    #[test]
+    fn statuses() {
+        let mut dashboard = Dashboard::new();
+        assert_eq!(dashboard.status(25), Status::NotImplemented);
+        assert_eq!(dashboard.status(6), Status::Unsolved);
+        run_now(&mut dashboard, 6);
+        assert_eq!(dashboard.status(6), Status::Solved);
+        assert_eq!(dashboard.runs[&(6, 1)].outcome, Ok(288));
+
+        dashboard.input = InputKind::Real;
+        dashboard.runs.insert(
+            (6, 2),
+            Run {
+                input: InputKind::Real,
+                outcome: Err("boom".to_string()),
+                expected: None,
+                elapsed: Duration::ZERO,
+                trace: Trace::default(),
+            },
+        );
+        assert_eq!(dashboard.status(6), Status::Failing);
+        assert_eq!(
+            dashboard.details(),
+            vec!["Day 1: Unsolved", "  part 1: not run", "  part 2: not run"]
+        );
+        dashboard.selected = 6;
+        assert_eq!(
+            dashboard.details()[2],
+            "  part 2: error: boom (Real input, 0.000ns)"
+        );
+        assert_eq!(dashboard.cell(6), "[ 6  x  ] ");
+    }
+
+    #[test]
+    fn answers_are_checked() {
+        let mut dashboard = Dashboard::new();
+        let run = |answer, expected| Run {
+            input: InputKind::Example,
+            outcome: Ok(answer),
+            expected,
+            elapsed: Duration::ZERO,
+            trace: Trace::default(),
+        };
+        dashboard.runs.insert((6, 1), run(288, Some(288)));
+        dashboard.runs.insert((6, 2), run(1, Some(71503)));
+        assert_eq!(dashboard.status(6), Status::Failing);
+        dashboard.selected = 6;
+        assert!(
+            dashboard.details()[2].starts_with("  part 2: 1, expected 71503")
+        );
+
+        // real input has no known answer
+        dashboard.runs.insert((6, 2), run(1, None));
+        assert_eq!(dashboard.status(6), Status::Ran);
+    }
+
+    #[test]
+    fn example_answers_are_right() {
+        for &(name, solver) in PUZZLES {
+            let (day, part) = name.split_once('.').unwrap();
+            let answer =
+                example_answer(day.parse().unwrap(), part.parse().unwrap());
+            assert_eq!(Some(solver(None)), answer, "puzzle {name}");
+        }
+    }
+
+    #[test]
+    fn explain_traces() {
+        let mut dashboard = Dashboard::new();
+        dashboard.selected = 6;
+        dashboard.explain = true;
+        run_now(&mut dashboard, 6);
+        assert_eq!(
+            dashboard.details()[2..5],
+            [
+                "    7 ms race, 9 mm record: hold 2..=5 ms",
+                "    15 ms race, 40 mm record: hold 4..=11 ms",
+                "    30 ms race, 200 mm record: hold 11..=19 ms",
+            ]
+        );
+        let trace = Trace {
+            steps: (0..TRACE_LINES + 1).map(|i| i.to_string()).collect(),
+            dropped: 4,
+        };
+        assert_eq!(
+            trace_lines(&trace)[TRACE_LINES],
+            "    ... and 5 more steps"
+        );
+    }
+
+    #[test]
+    fn selection_stays_on_calendar() {
+        let mut dashboard = Dashboard::new();
+        dashboard.move_by(-1, 0);
+        assert_eq!(dashboard.selected, 1);
+        dashboard.move_by(0, 1);
+        assert_eq!(dashboard.selected, 6);
+        dashboard.move_by(4, 3);
+        assert_eq!(dashboard.selected, 25);
+        dashboard.move_by(0, 1);
+        assert_eq!(dashboard.selected, 25);
+    }
+}